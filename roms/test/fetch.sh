@@ -0,0 +1,17 @@
+#!/bin/sh
+# fetch the third party test suites into roms/test/vendor, with their licences.
+# once each ROM's result screen has been checked by eye, its hash from
+# `c8rust test ROM --frames N` can be asserted in src/test/testrom_test.rs
+set -e
+cd "$(dirname "$0")"
+mkdir -p vendor/corax89 vendor/timendus
+
+corax=https://raw.githubusercontent.com/corax89/chip8-test-rom/master
+curl -fsSL -o vendor/corax89/test_opcode.ch8 "$corax/test_opcode.ch8"
+curl -fsSL -o vendor/corax89/README.md "$corax/README.md"
+
+timendus=https://raw.githubusercontent.com/Timendus/chip8-test-suite/main
+for rom in 3-corax+ 4-flags 5-quirks 6-keypad; do
+    curl -fsSL -o "vendor/timendus/$rom.ch8" "$timendus/bin/$rom.ch8"
+done
+curl -fsSL -o vendor/timendus/LICENSE "$timendus/LICENSE"
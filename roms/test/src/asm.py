@@ -0,0 +1,45 @@
+# tiny chip-8 assembler for the self-test ROMs
+import sys
+PASS = [0x00, 0x01, 0x02, 0x14, 0x08]
+FAIL = [0x88, 0x50, 0x20, 0x50, 0x88]
+
+class Asm:
+    def __init__(s):
+        s.code = []   # (kind, value)
+        s.labels = {}
+        s.slot = 0
+    def op(s, w): s.code.append(('op', w))
+    def ref(s, hi, label): s.code.append(('ref', (hi, label)))
+    def label(s, l): s.labels[l] = 0x200 + s.size()
+    def data(s, bs):
+        for b in bs: s.code.append(('byte', b))
+    def size(s):
+        n = 0
+        for k, v in s.code: n += 1 if k == 'byte' else 2
+        return n
+    def build(s):
+        out = []
+        for k, v in s.code:
+            if k == 'op': out += [v >> 8, v & 0xff]
+            elif k == 'byte': out.append(v)
+            else:
+                w = v[0] << 12 | s.labels[v[1]]
+                out += [w >> 8, w & 0xff]
+        return bytes(out)
+    # check: list of (reg, value). draws pass/fail sprite at next slot
+    def check(s, checks):
+        x = (s.slot % 8) * 8; y = (s.slot // 8) * 6
+        s.slot += 1
+        # copy vF into vE first so checks against vF see the flag as it was
+        s.op(0x8ef0)
+        s.ref(0xa, 'pass')
+        for r, v in checks:
+            if r == 0xf: r = 0xe
+            s.op(0x3000 | r << 8 | v)   # skip if vr == v
+            s.ref(0xa, 'fail')
+        s.op(0x6d00 | x); s.op(0x6c00 | y)   # vD = x, vC = y
+        s.op(0xddc5)
+    def end(s):
+        s.label('halt'); s.ref(0x1, 'halt')
+        s.label('pass'); s.data(PASS)
+        s.label('fail'); s.data(FAIL)
@@ -0,0 +1,37 @@
+# flags.ch8, the 8xy4/8xy5/8xy7/8xy6/8xyE results and vF: python3 flags.py ../flags.ch8
+import os, sys; sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
+from asm import Asm
+a = Asm()
+def ld(r, v): a.op(0x6000 | r << 8 | v)
+def alu(x, y, n): a.op(0x8000 | x << 8 | y << 4 | n)
+tests = []
+# (x val, y val, n, result, flag)
+cases = [
+  (0x21, 0x21, 4, 0x42, 0), (0xff, 0x01, 4, 0x00, 1), (0x80, 0x80, 4, 0x00, 1),
+  (0xf0, 0x0f, 4, 0xff, 0), (0x7f, 0x01, 4, 0x80, 0), (0xc0, 0x50, 4, 0x10, 1),
+  (0x63, 0x21, 5, 0x42, 1), (0x21, 0x63, 5, 0xbe, 0), (0x10, 0x10, 5, 0x00, 1),
+  (0x00, 0x01, 5, 0xff, 0), (0x80, 0x7f, 5, 0x01, 1), (0x7f, 0x80, 5, 0xff, 0),
+  (0x20, 0x62, 7, 0x42, 1), (0x62, 0x20, 7, 0xbe, 0), (0x33, 0x33, 7, 0x00, 1),
+  (0x01, 0x00, 7, 0xff, 0),
+]
+for xv, yv, n, res, fl in cases:
+    ld(0, xv); ld(1, yv); alu(0, 1, n)
+    a.check([(0, res), (0xf, fl)])
+# shifts: vy == vx so either quirk gives the same answer
+for v, n, res, fl in [(0x85, 6, 0x42, 1), (0x84, 6, 0x42, 0), (0x01, 6, 0x00, 1),
+                      (0x81, 0xe, 0x02, 1), (0x41, 0xe, 0x82, 0), (0x80, 0xe, 0x00, 1)]:
+    ld(0, v); ld(1, v); alu(0, 1, n)
+    a.check([(0, res), (0xf, fl)])
+# vF as the destination: the flag is written last and wins
+for fv, yv, n, fl in [(0xff, 0x01, 4, 1), (0x01, 0x01, 4, 0), (0x05, 0x01, 5, 1),
+                      (0x00, 0x01, 5, 0), (0x01, 0x05, 7, 1), (0x05, 0x01, 7, 0)]:
+    ld(0xf, fv); ld(1, yv); alu(0xf, 1, n)
+    a.check([(0xf, fl)])
+# vF as the operand: the flag is computed from its old value
+ld(0xf, 0x02); ld(0, 0xff); alu(0, 0xf, 4)
+a.check([(0, 0x01), (0xf, 1)])
+ld(0xf, 0x01); ld(0, 0x03); alu(0, 0xf, 5)
+a.check([(0, 0x02), (0xf, 1)])
+a.end()
+open(sys.argv[1], 'wb').write(a.build())
+print(a.slot, 'tests')
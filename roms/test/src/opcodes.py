@@ -0,0 +1,62 @@
+# opcodes.ch8, the rest of the instruction set: python3 opcodes.py ../opcodes.ch8
+import os, sys; sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
+from asm import Asm
+a = Asm()
+def ld(r, v): a.op(0x6000 | r << 8 | v)
+def alu(x, y, n): a.op(0x8000 | x << 8 | y << 4 | n)
+SCRATCH = 0xe00
+
+# 1: 3xnn skips on equal
+ld(0, 5); a.op(0x3005); ld(0, 0xee)
+a.check([(0, 5)])
+# 2: 4xnn skips on not equal
+ld(0, 5); a.op(0x4006); ld(0, 0xee)
+a.check([(0, 5)])
+# 3: 5xy0 skips on equal
+ld(0, 5); ld(1, 5); a.op(0x5010); ld(0, 0xee)
+a.check([(0, 5)])
+# 4: 9xy0 skips on not equal
+ld(0, 5); ld(1, 6); a.op(0x9010); ld(0, 0xee)
+a.check([(0, 5)])
+# 5: 7xnn wraps without touching vF
+ld(0xf, 0); ld(0, 0xff); a.op(0x7002)
+a.check([(0, 1), (0xf, 0)])
+# 6-8: logic ops
+ld(0, 0x42); ld(1, 0x0f); alu(0, 1, 1); a.check([(0, 0x4f)])
+ld(0, 0x42); ld(1, 0x0f); alu(0, 1, 2); a.check([(0, 0x02)])
+ld(0, 0x42); ld(1, 0xff); alu(0, 1, 3); a.check([(0, 0xbd)])
+# 9: nested call / return
+ld(0, 0); a.ref(0x2, 'sub1')
+a.check([(0, 3)])
+# 10: fx33 bcd
+ld(0, 234); a.op(0xa000 | SCRATCH); a.op(0xf033); ld(0, 0); a.op(0xf265)
+a.check([(0, 2), (1, 3), (2, 4)])
+# 11: fx55 / fx65 round trip
+ld(0, 0x11); ld(1, 0x22); ld(2, 0x33); a.op(0xa000 | SCRATCH); a.op(0xf255)
+ld(0, 0); ld(1, 0); ld(2, 0); a.op(0xa000 | SCRATCH); a.op(0xf265)
+a.check([(0, 0x11), (1, 0x22), (2, 0x33)])
+# 12: fx1e uses the full 12-bit I
+ld(0, 0x5a); a.op(0xa000 | SCRATCH + 0x10); a.op(0xf055)
+ld(0, 0x10); a.op(0xa000 | SCRATCH); a.op(0xf01e); ld(0, 0); a.op(0xf065)
+a.check([(0, 0x5a)])
+# 13: fx29 points at the font
+ld(0, 0xa); a.op(0xf029); a.op(0xf065)
+a.check([(0, 0xf0)])
+# 14: fx15 / fx07 delay timer (a frame may end in between, so 0xff or 0xfe)
+ld(0, 0xff); a.op(0xf015); a.op(0xf107); ld(2, 1); alu(1, 2, 1)
+a.check([(1, 0xff)])
+# 15: bnnn jumps to nnn + v0
+ld(0, 4); ld(1, 0); a.ref(0xb, 'table')
+a.label('back')
+a.check([(1, 7)])
+# 16: dxyn sets vF only on collision (drawn twice, leaving the screen clean)
+a.op(0xa000 | 0x000); ld(0, 40); ld(1, 26); a.op(0xd015); a.op(0x82f0)  # v2 = vF
+a.op(0xd015)
+a.check([(2, 0), (0xf, 1)])
+a.end()
+# subroutines and jump table after the end
+a.label('sub1'); a.ref(0x2, 'sub2'); a.op(0x7002); a.op(0x00ee)
+a.label('sub2'); a.op(0x7001); a.op(0x00ee)
+a.label('table'); ld(1, 1); a.ref(0x1, 'back'); ld(1, 7); a.ref(0x1, 'back')
+open(sys.argv[1], 'wb').write(a.build())
+print(a.slot, 'tests')
@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{Error, ErrorKind, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+// file extensions listed as ROMs in directories and archives
+pub const EXTENSIONS: [&str; 3] = ["ch8", "sc8", "xo8"];
+
+fn is_rom(name: &str) -> bool {
+    Path::new(name).extension().and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+fn is_zip(path: &str) -> bool {
+    let mut magic = [0; 4];
+    path.to_lowercase().ends_with(".zip")
+        || File::open(path).and_then(|mut f| f.read_exact(&mut magic)).is_ok() && magic == *b"PK\x03\x04"
+}
+
+// "archive.zip:game.ch8" names a ROM inside an archive
+pub fn split(path: &str) -> Option<(&str, &str)> {
+    // search the bytes themselves: lowercasing can move non-ASCII characters
+    let at = path.as_bytes().windows(5).position(|w| w.eq_ignore_ascii_case(b".zip:"))? + ".zip".len();
+    Some((&path[..at], &path[at + 1..]))
+}
+
+// a ROM file's contents, or a member of an archive
+pub fn read(path: &str) -> Result<Vec<u8>, Error> {
+    match split(path) {
+        Some((zip, member)) => read_member(File::open(zip)?, member),
+        None                => fs::read(path),
+    }
+}
+
+pub fn read_member<R: Read + Seek>(zip: R, member: &str) -> Result<Vec<u8>, Error> {
+    let mut z = ZipArchive::new(zip)?;
+    let mut f = z.by_name(member).map_err(|e| match e {
+        zip::result::ZipError::FileNotFound => Error::new(ErrorKind::NotFound, format!("no {} in the archive", member)),
+        e => e.into(),
+    })?;
+    let mut buf = Vec::new();
+    f.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// the ROMs in a directory (and those below it) or an archive, as paths
+// that can be loaded; None for anything else
+pub fn list(path: &str) -> Result<Option<Vec<String>>, Error> {
+    let p = Path::new(path);
+    let mut roms = if p.is_dir() {
+        let mut roms = Vec::new();
+        walk(p, &mut BTreeSet::new(), &mut roms)?;
+        roms
+    } else if split(path).is_none() && p.is_file() && is_zip(path) {
+        list_members(File::open(path)?)?.into_iter().map(|m| format!("{}:{}", path, m)).collect()
+    } else {
+        return Ok(None);
+    };
+    roms.sort();
+    Ok(Some(roms))
+}
+
+pub fn list_members<R: Read + Seek>(zip: R) -> Result<Vec<String>, Error> {
+    let z = ZipArchive::new(zip)?;
+    let mut names: Vec<String> = z.file_names().filter(|n| is_rom(n)).map(String::from).collect();
+    names.sort();
+    Ok(names)
+}
+
+// seen holds the directories already walked, which symlinks can lead back to
+fn walk(dir: &Path, seen: &mut BTreeSet<PathBuf>, roms: &mut Vec<String>) -> Result<(), Error> {
+    if !seen.insert(dir.canonicalize()?) {
+        return Ok(());
+    }
+    for e in fs::read_dir(dir)? {
+        let p = e?.path();
+        let name = p.to_string_lossy().into_owned();
+        if p.is_dir() {
+            walk(&p, seen, roms)?;
+        } else if name.to_lowercase().ends_with(".zip") {
+            // an archive that can't be read is left out, rather than the whole directory
+            if let Ok(members) = File::open(&p).and_then(list_members) {
+                roms.extend(members.into_iter().map(|m| format!("{}:{}", name, m)));
+            }
+        } else if is_rom(&name) {
+            roms.push(name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "test/archive_test.rs"]
+mod archive_test;
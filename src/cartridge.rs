@@ -0,0 +1,94 @@
+use std::io::{Error, ErrorKind};
+
+use serde_json::{Map, Value};
+
+use crate::octo;
+
+// Octo "cartridge" GIFs carry a JSON payload, {"options": {..}, "program": ".."},
+// one nybble in the low bits of each pixel's palette index, high nybble first,
+// after a 32-bit big endian length. the high bits draw the label, and the
+// payload continues from frame to frame
+pub fn is_cartridge(d: &[u8]) -> bool {
+    d.starts_with(b"GIF87a") || d.starts_with(b"GIF89a")
+}
+
+fn bad(what: String) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("cartridge: {}", what))
+}
+
+pub type Rgb = (u8, u8, u8);
+
+// the options Octo saves with a program that we can follow
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    pub ipf:        Option<usize>,          // "tickrate", cycles per frame
+    pub quirks:     Vec<(&'static str, bool)>,
+    pub fg:         Option<Rgb>,            // "fillColor"
+    pub bg:         Option<Rgb>,            // "backgroundColor"
+    pub buzz:       Option<Rgb>,            // "buzzColor", the background while the buzzer sounds
+}
+
+// the program and options in a cartridge
+pub fn load(d: &[u8]) -> Result<(Vec<u8>, Options), Error> {
+    let payload = payload(d)?;
+    let j: Value = serde_json::from_str(&payload).map_err(|e| bad(format!("invalid payload: {}", e)))?;
+    let program = j.get("program").and_then(Value::as_str).ok_or_else(|| bad("no program".to_string()))?;
+    let options = match j.get("options").and_then(Value::as_object) {
+        Some(o) => options(o),
+        None    => Options::default(),
+    };
+    // cartridges hold Octo source, not a binary
+    let program = octo::compile(program).map_err(|e| bad(format!("compiling the program: {}", e)))?;
+    Ok((program, options))
+}
+
+// the JSON text hidden in the image
+pub fn payload(d: &[u8]) -> Result<String, Error> {
+    let mut opts = gif::DecodeOptions::new();
+    opts.set_color_output(gif::ColorOutput::Indexed);
+    let mut dec = opts.read_info(d).map_err(|e| bad(e.to_string()))?;
+    let mut nybbles = Vec::new();
+    while let Some(f) = dec.read_next_frame().map_err(|e| bad(e.to_string()))? {
+        nybbles.extend(f.buffer.iter().map(|p| p & 0xf));
+    }
+    let bytes: Vec<u8> = nybbles.chunks_exact(2).map(|n| n[0] << 4 | n[1]).collect();
+    let len = match bytes.get(..4) {
+        Some(b) => u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize,
+        None    => return Err(bad("no payload".to_string())),
+    };
+    let text = bytes.get(4..4 + len).ok_or_else(|| bad("payload is cut short".to_string()))?;
+    // Octo writes one byte per character
+    Ok(text.iter().map(|b| *b as char).collect())
+}
+
+fn options(o: &Map<String, Value>) -> Options {
+    let color = |k: &str| o.get(k).and_then(Value::as_str).and_then(rgb);
+    // Octo's names for our quirks; its load/store quirk is our memory quirk turned off
+    let quirks = [
+        ("logicQuirks",     "vf_reset", false),
+        ("loadStoreQuirks", "memory",   true),
+        ("shiftQuirks",     "shifting", false),
+        ("jumpQuirks",      "jumping",  false),
+        ("clipQuirks",      "clipping", false),
+    ].iter().filter_map(|(octo, ours, invert)| {
+        o.get(*octo).and_then(Value::as_bool).map(|on| (*ours, on != *invert))
+    }).collect();
+    Options {
+        ipf:    o.get("tickrate").and_then(Value::as_u64).filter(|n| *n > 0).map(|n| n as usize),
+        quirks,
+        fg:     color("fillColor"),
+        bg:     color("backgroundColor"),
+        buzz:   color("buzzColor"),
+    }
+}
+
+// "#rrggbb"
+fn rgb(s: &str) -> Option<Rgb> {
+    let h = s.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let c = |i: usize| u8::from_str_radix(h.get(i..i + 2)?, 16).ok();
+    Some((c(0)?, c(2)?, c(4)?))
+}
+
+#[cfg(test)]
+#[path = "test/cartridge_test.rs"]
+pub mod cartridge_test;
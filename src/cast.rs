@@ -0,0 +1,117 @@
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{self, BufWriter, Write},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// asciinema v2 recording
+// https://github.com/asciinema/asciinema/blob/develop/doc/asciicast-v2.md
+//
+// the first line is a JSON header, every following line is an output event:
+//   [time, "o", data]
+pub struct Cast<W: Write> {
+    out:    W,
+}
+
+impl Cast<BufWriter<File>> {
+    pub fn create(path: &str, width: u16, height: u16) -> Result<Self, io::Error> {
+        let f = File::create(path)?;
+        Cast::new(BufWriter::new(f), width, height)
+    }
+}
+
+impl<W: Write> Cast<W> {
+    pub fn new(mut out: W, width: u16, height: u16) -> Result<Self, io::Error> {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}",
+            width, height, stamp
+        )?;
+        Ok(Cast { out })
+    }
+
+    // one output event at time t (seconds since the start of the recording)
+    pub fn event(&mut self, t: f64, data: &[u8]) -> Result<(), io::Error> {
+        writeln!(self.out, "[{:.6}, \"o\", \"{}\"]", t, escape(&String::from_utf8_lossy(data)))
+    }
+
+    pub fn flush(&mut self) -> Result<(), io::Error> {
+        self.out.flush()
+    }
+}
+
+// JSON string escaping
+fn escape(s: &str) -> String {
+    let mut e = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"'  => e.push_str("\\\""),
+            '\\' => e.push_str("\\\\"),
+            '\n' => e.push_str("\\n"),
+            '\r' => e.push_str("\\r"),
+            '\t' => e.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => e.push_str(&format!("\\u{:04x}", c as u32)),
+            c    => e.push(c),
+        }
+    }
+    e
+}
+
+// pass-through writer that mirrors everything written to the terminal into a cast.
+// the backend writes in many small pieces, so output is buffered and emitted as
+// a single event per flush (tui flushes once per drawn frame).
+// timestamps come from the shared emulated clock rather than the wall clock
+pub struct Recorder<W: Write> {
+    inner:  W,
+    cast:   Option<Cast<BufWriter<File>>>,
+    clock:  Rc<Cell<f64>>,
+    buf:    Vec<u8>,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(inner: W, cast: Option<Cast<BufWriter<File>>>, clock: Rc<Cell<f64>>) -> Self {
+        Recorder {
+            inner,
+            cast,
+            clock,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for Recorder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(data)?;
+        if self.cast.is_some() { self.buf.extend_from_slice(&data[..n]); }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(c) = &mut self.cast {
+            if !self.buf.is_empty() {
+                c.event(self.clock.get(), &self.buf)?;
+                c.flush()?;
+                self.buf.clear();
+            }
+        }
+        self.inner.flush()
+    }
+}
+
+// the terminal is restored by the writers wrapping this one as they're dropped:
+// send that out before anything else is printed
+impl<W: Write> Drop for Recorder<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+#[path = "test/cast_test.rs"]
+mod cast_test;
@@ -0,0 +1,167 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use crate::cpu::{Cpu, RAM_SIZE};
+
+// how a search narrows down its candidates, comparing RAM to the last snapshot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    Equal(u8),
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl Filter {
+    fn keep(&self, old: u8, new: u8) -> bool {
+        match *self {
+            Filter::Equal(v)  => new == v,
+            Filter::Changed   => new != old,
+            Filter::Unchanged => new == old,
+            Filter::Increased => new > old,
+            Filter::Decreased => new < old,
+        }
+    }
+}
+
+// classic RAM search: start with every address, then filter down
+pub struct Search {
+    pub candidates: Vec<usize>,
+    snapshot:       Vec<u8>,
+}
+
+impl Search {
+    pub fn new(ram: &[u8]) -> Self {
+        Search {
+            candidates: (0..RAM_SIZE).collect(),
+            snapshot:   ram.to_vec(),
+        }
+    }
+
+    // keep the candidates matching the filter, and snapshot RAM for the next one
+    pub fn filter(&mut self, ram: &[u8], f: Filter) {
+        let old = &self.snapshot;
+        self.candidates.retain(|a| f.keep(old[*a], ram[*a]));
+        self.snapshot = ram.to_vec();
+    }
+
+    // value at the last snapshot
+    pub fn old(&self, addr: usize) -> u8 {
+        self.snapshot[addr]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cheat {
+    pub addr:   usize,
+    pub value:  u8,
+    pub freeze: bool,       // written every frame, otherwise once when loaded or set
+    pub name:   String,
+}
+
+// cheats for every ROM in a cheat file, keyed by the ROM's SHA-1:
+//
+//   [a9993e364706816aba3e25717850c26c9cd0d89d]
+//   freeze 2f4 03 lives
+//   set 300 ff
+#[derive(Default)]
+pub struct Cheats {
+    path:       Option<String>,
+    rom:        String,
+    others:     BTreeMap<String, Vec<Cheat>>,   // other ROMs' cheats, kept when saving
+    pub list:   Vec<Cheat>,                     // cheats for this ROM
+}
+
+impl Cheats {
+    // cheats for the ROM with the given SHA-1, from a cheat file if there is one yet
+    pub fn load(path: Option<&str>, rom: &str) -> Result<Self, Error> {
+        let mut all = match path {
+            Some(p) => match fs::read_to_string(p) {
+                Ok(s)  => parse(&s).map_err(|e| Error::new(e.kind(), format!("{}: {}", p, e)))?,
+                Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+                Err(e) => return Err(e),
+            },
+            None => BTreeMap::new(),
+        };
+        Ok(Cheats {
+            path:   path.map(String::from),
+            rom:    rom.to_string(),
+            list:   all.remove(rom).unwrap_or_default(),
+            others: all,
+        })
+    }
+
+    // write back the cheat file, if there is one
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(p) => p,
+            None    => return Ok(()),
+        };
+        let mut all = self.others.clone();
+        if !self.list.is_empty() { all.insert(self.rom.clone(), self.list.clone()); }
+        fs::write(path, to_text(&all))
+    }
+
+    pub fn add(&mut self, addr: usize, value: u8) {
+        self.list.retain(|ch| ch.addr != addr);
+        self.list.push(Cheat { addr, value, freeze: true, name: String::new() });
+    }
+
+    // every cheat, on load
+    pub fn apply_all(&self, c: &mut Cpu) {
+        for ch in &self.list { c.poke(ch.addr, ch.value); }
+    }
+
+    // frozen values, every frame
+    pub fn apply(&self, c: &mut Cpu) {
+        for ch in self.list.iter().filter(|ch| ch.freeze) { c.poke(ch.addr, ch.value); }
+    }
+}
+
+pub fn parse(s: &str) -> Result<BTreeMap<String, Vec<Cheat>>, Error> {
+    let mut all: BTreeMap<String, Vec<Cheat>> = BTreeMap::new();
+    let mut rom: Option<String> = None;
+    for (n, l) in s.lines().enumerate() {
+        let bad = || Error::new(ErrorKind::InvalidData, format!("line {}: invalid cheat \"{}\"", n + 1, l));
+        let l = l.trim();
+        if l.is_empty() || l.starts_with('#') { continue; }
+        if let Some(h) = l.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            rom = Some(h.to_lowercase());
+            continue;
+        }
+        let mut f = l.splitn(4, char::is_whitespace);
+        let freeze = match f.next() {
+            Some("freeze") => true,
+            Some("set")    => false,
+            _              => return Err(bad()),
+        };
+        let addr = f.next().and_then(|a| usize::from_str_radix(a, 16).ok()).filter(|a| *a < RAM_SIZE).ok_or_else(bad)?;
+        let value = f.next().and_then(|v| u8::from_str_radix(v, 16).ok()).ok_or_else(bad)?;
+        let name = f.next().unwrap_or("").trim().to_string();
+        let rom = rom.clone().ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("line {}: cheat before any [rom]", n + 1)))?;
+        all.entry(rom).or_default().push(Cheat { addr, value, freeze, name });
+    }
+    Ok(all)
+}
+
+pub fn to_text(all: &BTreeMap<String, Vec<Cheat>>) -> String {
+    let mut s = String::from("# c8rust cheats, by SHA-1 of the ROM\n");
+    for (rom, list) in all {
+        s.push_str(&format!("\n[{}]\n", rom));
+        for ch in list {
+            let kind = if ch.freeze { "freeze" } else { "set" };
+            let line = format!("{} {:03x} {:02x} {}", kind, ch.addr, ch.value, ch.name);
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+#[path = "test/cheat_test.rs"]
+mod cheat_test;
@@ -0,0 +1,156 @@
+use std::{
+    fmt::Write,
+    io::{Error, ErrorKind},
+};
+
+use crate::{
+    cpu::{PC_BASE, RAM_SIZE},
+    instruction::Instruction,
+};
+
+// how each byte of RAM was used
+const EXEC:  u8 = 0b001;    // fetched as part of an instruction
+const READ:  u8 = 0b010;    // read as data (dxyn sprite rows, fx65)
+const WRITE: u8 = 0b100;    // written (fx33, fx55)
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Annotated,  // disassembly with execution counts and read/write marks
+    Lcov,       // lcov tracefile, one DA line per instruction
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "annotated" => Ok(Format::Annotated),
+            "lcov"      => Ok(Format::Lcov),
+            _           => Err(Error::new(ErrorKind::InvalidInput, format!("unknown coverage format \"{}\"", name))),
+        }
+    }
+}
+
+// one line of the program listing
+#[derive(Debug, PartialEq)]
+struct Line {
+    addr:   usize,
+    code:   bool,           // a 2-byte instruction, otherwise a data byte
+}
+
+// which bytes of the program were executed, read and written
+pub struct Coverage {
+    hits:   Vec<u64>,       // executions per instruction address
+    flags:  Vec<u8>,
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Coverage::new()
+    }
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage {
+            hits:   vec![0; RAM_SIZE],
+            flags:  vec![0; RAM_SIZE],
+        }
+    }
+
+    // the instruction at pc was executed
+    pub fn execute(&mut self, pc: usize) {
+        self.hits[pc % RAM_SIZE] += 1;
+        self.flags[pc % RAM_SIZE] |= EXEC;
+        self.flags[(pc + 1) % RAM_SIZE] |= EXEC;
+    }
+
+    pub fn read(&mut self, addr: usize) {
+        self.flags[addr % RAM_SIZE] |= READ;
+    }
+
+    pub fn write(&mut self, addr: usize) {
+        self.flags[addr % RAM_SIZE] |= WRITE;
+    }
+
+    // split the program into instructions and data.
+    // executed bytes are instructions and bytes read or written are data;
+    // anything never touched is shown as an instruction if it decodes,
+    // so a sprite that is never drawn may appear as unexecuted code
+    fn listing(&self, ram: &[u8], len: usize) -> Vec<Line> {
+        // also cover bytes past the end of the ROM image that the program used
+        let used = self.flags.iter().rposition(|f| *f != 0).map_or(0, |a| a + 1);
+        let end = (PC_BASE + len).max(used).min(RAM_SIZE);
+
+        let mut lines = Vec::new();
+        let mut addr = PC_BASE;
+        while addr < end {
+            let code = if self.flags[addr] & EXEC != 0 {
+                self.hits[addr] > 0
+            } else {
+                let op = (ram[addr] as u16) << 8 | ram[(addr + 1) % RAM_SIZE] as u16;
+                addr + 1 < end
+                    && self.flags[addr] == 0
+                    && self.flags[addr + 1] == 0
+                    && op != 0
+                    && Instruction::decode(op).is_some()
+            };
+            lines.push(Line { addr, code });
+            addr += if code { 2 } else { 1 };
+        }
+        lines
+    }
+
+    // report over the program, len bytes loaded at PC_BASE.
+    // name is the ROM path, used as the lcov source file
+    pub fn report(&self, ram: &[u8], len: usize, format: Format, name: &str) -> String {
+        let lines = self.listing(ram, len);
+        match format {
+            Format::Annotated => self.annotated(ram, &lines),
+            Format::Lcov      => self.lcov(&lines, name),
+        }
+    }
+
+    fn annotated(&self, ram: &[u8], lines: &[Line]) -> String {
+        let code: Vec<&Line> = lines.iter().filter(|l| l.code).collect();
+        let executed = code.iter().filter(|l| self.hits[l.addr] > 0).count();
+        let count = |flag: u8| lines.iter().filter(|l| !l.code && self.flags[l.addr] & flag != 0).count();
+
+        let mut s = String::new();
+        let _ = writeln!(s, "{}/{} instructions executed ({:.1}%), {} bytes read, {} bytes written",
+                         executed, code.len(), 100.0 * executed as f64 / code.len().max(1) as f64,
+                         count(READ), count(WRITE));
+        let _ = writeln!(s, "\n      count xrw addr bytes");
+        for l in lines {
+            let f = self.flags[l.addr] | if l.code { self.flags[(l.addr + 1) % RAM_SIZE] } else { 0 };
+            let marks: String = [(EXEC, 'x'), (READ, 'r'), (WRITE, 'w')].iter()
+                .map(|(b, c)| if f & b != 0 { *c } else { '-' })
+                .collect();
+            if l.code {
+                let op = (ram[l.addr] as u16) << 8 | ram[(l.addr + 1) % RAM_SIZE] as u16;
+                let inst = Instruction::decode(op).map(|i| i.to_string()).unwrap_or_default();
+                let _ = writeln!(s, "{:>11} {} {:03x}  {:04x} {}", self.hits[l.addr], marks, l.addr, op, inst);
+            } else {
+                let b = ram[l.addr];
+                let bits: String = (0..8).rev().map(|ii| if b >> ii & 1 == 1 { '#' } else { '.' }).collect();
+                let _ = writeln!(s, "{:>11} {} {:03x}  {:02x}   {}", "", marks, l.addr, b, bits);
+            }
+        }
+        s
+    }
+
+    // lcov has no notion of addresses: each instruction's address is its line number
+    fn lcov(&self, lines: &[Line], name: &str) -> String {
+        let mut s = format!("TN:\nSF:{}\n", name);
+        let (mut found, mut hit) = (0, 0);
+        for l in lines.iter().filter(|l| l.code) {
+            let _ = writeln!(s, "DA:{},{}", l.addr, self.hits[l.addr]);
+            found += 1;
+            if self.hits[l.addr] > 0 { hit += 1; }
+        }
+        let _ = writeln!(s, "LF:{}\nLH:{}\nend_of_record", found, hit);
+        s
+    }
+}
+
+#[cfg(test)]
+#[path = "test/coverage_test.rs"]
+mod coverage_test;
@@ -1,11 +1,18 @@
-extern crate rand;
-
-use std::io::{Error, ErrorKind};
+use std::{
+    collections::BTreeSet,
+    io::{Error, ErrorKind},
+};
 
 use crate::{
     screen::V_WIDTH,
     screen::V_HEIGHT,
     rom::ROM,
+    rng::{Random, Seeded, DEFAULT_SEED},
+    instruction::Instruction,
+    trace::{Record, Regs, Tracer},
+    profile::Profile,
+    coverage::{self, Coverage},
+    quirks::Quirks,
 };
 
 const OP_LEN: usize = 2; // number of words in an opcode
@@ -110,7 +117,7 @@ pub const FONT: [u8; 80] = [
     0x90, /* #  #    */
     0xe0, /* ###     */
 
-    0x0f, /* ####    */
+    0xf0, /* ####    */
     0x80, /* #       */
     0xf0, /* ####    */
     0x80, /* #       */
@@ -135,6 +142,28 @@ pub const STACK_SIZE: usize = 128;
 pub const RAM_SIZE:   usize = 4096;
 pub const REG_COUNT:  usize = 16;
 pub const PC_BASE:    usize = 0x200;
+pub const TIMER_HZ:   u64   = 60;     // timers count down once per frame
+pub const CYCLES_PER_FRAME: usize = 10; // instructions per 60 Hz frame (~600 Hz)
+
+// machine state, saved and loaded from the pause menu
+pub struct Snapshot {
+    ram:        [u8; RAM_SIZE],
+    vram:       [[u8; V_HEIGHT]; V_WIDTH],
+    sp:         usize,
+    s:          [usize; STACK_SIZE],
+    v:          [u8; REG_COUNT],
+    i:          usize,
+    pc:         usize,
+    kreg:       usize,
+    delay:      u8,
+    sound:      u8,
+    kwait:      bool,
+    cycles:     u64,
+    frames:     u64,
+    fcycle:     usize,
+    rng:        Box<dyn Random>,
+}
+
 pub struct Cpu {
     /* memory */
     ram:        [u8; RAM_SIZE],             // RAM tape
@@ -155,16 +184,30 @@ pub struct Cpu {
     sound:      u8,                         // sound timer
 
     /* emulator state */
-    verbose:    bool,                       // print debug information
     keyb:       [bool; 16],                 // key reader
     kwait:      bool,                       // waiting for key press
     prog:       Option<ROM>,                // store program (restore from reset)
+    cycles:     u64,                        // machine cycles since reset
+    frames:     u64,                        // frames since reset (emulated clock)
+    ipf:        usize,                      // machine cycles per frame
+    quirks:     Quirks,                     // interpreter behaviour to follow
+    seed:       u64,                        // RNG seed (restored on reset, recorded in movies)
+    rng:        Box<dyn Random>,            // random source for cxnn
+    tracer:     Option<Tracer>,             // execution trace
+    writes:     Vec<(usize, u8)>,           // RAM written by the current instruction (when tracing)
+    profile:    Option<Profile>,            // execution statistics
+    coverage:   Option<Coverage>,           // bytes executed, read and written
+    written:    Vec<u64>,                   // frame of the last write to each address, plus one
+    fcycle:     usize,                      // machine cycles run in the current frame
+    breakpoints: BTreeSet<usize>,           // addresses to stop before executing
+    hit:        Option<usize>,              // stopped at this breakpoint
+    sprite:     (usize, Vec<u8>),           // address and rows drawn by the last dxyn
 }
 impl Cpu {
-    pub fn new(prog: Option<ROM>, v: bool) -> Result<Self, Error> {
+    pub fn new(prog: Option<ROM>) -> Result<Self, Error> {
         let ram = Cpu::ram_init(&prog)?;
         Ok(Cpu {
-            ram:        ram,
+            ram,
             vram:       [[0; V_HEIGHT]; V_WIDTH],
             sp:         0x0,
             s:          [0; STACK_SIZE],
@@ -174,28 +217,38 @@ impl Cpu {
             kreg:       0,
             delay:      0,
             sound:      0,
-            verbose:    v,
             keyb:       [false; 16],
             kwait:      false,
-            prog:       prog,
+            prog,
+            cycles:     0,
+            frames:     0,
+            ipf:        CYCLES_PER_FRAME,
+            quirks:     Quirks::default(),
+            seed:       DEFAULT_SEED,
+            rng:        Box::new(Seeded::new(DEFAULT_SEED)),
+            tracer:     None,
+            writes:     Vec::new(),
+            profile:    None,
+            coverage:   None,
+            written:    vec![0; RAM_SIZE],
+            fcycle:     0,
+            breakpoints: BTreeSet::new(),
+            hit:        None,
+            sprite:     (0, Vec::new()),
         })
     }
 
     fn ram_init(rom: &Option<ROM>) -> Result<[u8; RAM_SIZE], Error> {
         let mut ram = [0; RAM_SIZE];
         // always initialize font in RAM
-        for ii in 0..FONT.len() {
-            ram[ii] = FONT[ii]; // XXX: is this a memcpy?
-        }
+        ram[..FONT.len()].copy_from_slice(&FONT);
         match rom {
             None => { },
             Some(r) => {
                 if PC_BASE + r.rom.len() > RAM_SIZE {
                     return Err(Error::new(ErrorKind::InvalidData, "Program exceeds RAM!"));
                 }
-                for ii in 0..r.rom.len() {
-                    ram[PC_BASE + ii] = r.rom[ii];
-                }
+                ram[PC_BASE..PC_BASE + r.rom.len()].copy_from_slice(&r.rom);
             },
         };
         Ok(ram)
@@ -205,11 +258,11 @@ impl Cpu {
     #[allow(dead_code)]
     fn opcode_init(&mut self, op: u16) {
         self.ram[self.pc]     = ((op & 0xff00) >> 8) as u8;
-        self.ram[self.pc + 1] = ((op & 0x00ff) >> 0) as u8;
+        self.ram[self.pc + 1] = (op & 0x00ff) as u8;
     }
 
-    // TODO: reset in keyhandling
-    fn reset(&mut self) {
+    // back to power on, keeping the machine's settings and debugging state
+    pub fn reset(&mut self) {
         // safe unwrap:
         // in order to reset,the program must have been valid
         // already on initialization of the CPU
@@ -226,108 +279,405 @@ impl Cpu {
         self.sound  = 0;
         self.keyb   = [false; 16];
         self.kwait  = false;
+        self.cycles = 0;
+        self.frames = 0;
+        self.written = vec![0; RAM_SIZE];
+        self.fcycle = 0;
+        self.hit    = None;
+        self.sprite = (0, Vec::new());
+        self.rng.reseed(self.seed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ram:    self.ram,
+            vram:   self.vram,
+            sp:     self.sp,
+            s:      self.s,
+            v:      self.v,
+            i:      self.i,
+            pc:     self.pc,
+            kreg:   self.kreg,
+            delay:  self.delay,
+            sound:  self.sound,
+            kwait:  self.kwait,
+            cycles: self.cycles,
+            frames: self.frames,
+            fcycle: self.fcycle,
+            rng:    self.rng.clone_box(),
+        }
+    }
+
+    pub fn restore(&mut self, st: &Snapshot) {
+        self.ram    = st.ram;
+        self.vram   = st.vram;
+        self.sp     = st.sp;
+        self.s      = st.s;
+        self.v      = st.v;
+        self.i      = st.i;
+        self.pc     = st.pc;
+        self.kreg   = st.kreg;
+        self.delay  = st.delay;
+        self.sound  = st.sound;
+        self.kwait  = st.kwait;
+        self.cycles = st.cycles;
+        self.frames = st.frames;
+        self.fcycle = st.fcycle;
+        self.rng    = st.rng.clone_box();
+        self.hit    = None;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // restart the random sequence from a known seed (deterministic replay)
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng.reseed(seed);
+    }
+
+    // swap the random source, keeping the current seed
+    pub fn set_rng(&mut self, mut rng: Box<dyn Random>) {
+        rng.reseed(self.seed);
+        self.rng = rng;
+    }
+
+    pub fn rng_name(&self) -> &'static str {
+        self.rng.name()
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, q: Quirks) {
+        self.quirks = q;
+    }
+
+    pub fn ipf(&self) -> usize {
+        self.ipf
+    }
+
+    // machine cycles per frame, at least one
+    pub fn set_ipf(&mut self, ipf: usize) {
+        self.ipf = ipf.max(1);
+        self.fcycle = self.fcycle.min(self.ipf - 1);
+    }
+
+    // write to RAM from an instruction, noting it for the trace
+    fn store(&mut self, addr: usize, val: u8) {
+        self.ram[addr] = val;
+        if self.tracer.is_some() { self.writes.push((addr, val)); }
+        if let Some(cv) = &mut self.coverage { cv.write(addr); }
+        self.written[addr] = self.frames + 1;
+    }
+
+    // read RAM as data from an instruction
+    fn load(&mut self, addr: usize) -> u8 {
+        if let Some(cv) = &mut self.coverage { cv.read(addr); }
+        self.ram[addr]
     }
 
     fn fetch(&self) -> u16 {
         (self.ram[self.pc] as u16) << 8 | (self.ram[self.pc + 1] as u16)
     }
 
+    /* debugger access {{{ */
+    pub fn ram(&self) -> &[u8; RAM_SIZE] {
+        &self.ram
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn index(&self) -> usize {
+        self.i
+    }
+
+    // frames since an instruction last wrote to addr
+    pub fn written_ago(&self, addr: usize) -> Option<u64> {
+        match self.written[addr % RAM_SIZE] {
+            0 => None,
+            f => Some(self.frames + 1 - f),
+        }
+    }
+
+    // edit RAM from outside the program
+    pub fn poke(&mut self, addr: usize, val: u8) {
+        self.ram[addr % RAM_SIZE] = val;
+    }
+
+    // return addresses, outermost call first
+    pub fn stack(&self) -> &[usize] {
+        &self.s[1..=self.sp]
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<usize> {
+        &self.breakpoints
+    }
+
+    // returns whether there is now a breakpoint at addr
+    pub fn toggle_breakpoint(&mut self, addr: usize) -> bool {
+        if !self.breakpoints.remove(&addr) { self.breakpoints.insert(addr); }
+        self.breakpoints.contains(&addr)
+    }
+
+    // address and rows of the last sprite drawn, as they were at the time
+    pub fn last_sprite(&self) -> (usize, &[u8]) {
+        (self.sprite.0, &self.sprite.1)
+    }
+
+    // keypad state the last instruction saw
+    pub fn keys(&self) -> &[bool; 16] {
+        &self.keyb
+    }
+
+    // the breakpoint frame() stopped at, if it did
+    pub fn at_breakpoint(&self) -> Option<usize> {
+        self.hit
+    }
+
+    // part way through a frame, stopped at a breakpoint or stepped into it
+    pub fn mid_frame(&self) -> bool {
+        self.fcycle != 0
+    }
+    /* }}} */
+
+    // emulated time since reset, in seconds
+    pub fn clock(&self) -> f64 {
+        self.frames as f64 / TIMER_HZ as f64
+    }
+
+    // sound timer running, the buzzer should be on
+    pub fn buzzing(&self) -> bool {
+        self.sound > 0
+    }
+
+    // machine cycles and frames run since reset
+    pub fn counts(&self) -> (u64, u64) {
+        (self.cycles, self.frames)
+    }
+
+    // registers, timers and the instruction at PC, for a crash report.
+    // anything may be out of range after a panic
+    pub fn state(&self) -> String {
+        let op = match (self.ram.get(self.pc), self.ram.get(self.pc + 1)) {
+            (Some(hi), Some(lo)) => {
+                let op = (*hi as u16) << 8 | *lo as u16;
+                let inst = Instruction::decode(op).map_or(String::from("invalid"), |i| i.to_string());
+                format!("{:04x}  {}", op, inst)
+            },
+            _ => String::from("outside RAM"),
+        };
+        let v: Vec<String> = self.v.iter().map(|b| format!("{:02x}", b)).collect();
+        let stack: Vec<String> = self.s.get(1..=self.sp).unwrap_or(&[]).iter().map(|a| format!("{:03x}", a)).collect();
+        format!(
+            "pc  {:03x}  {}\nv   {}\ni   {:03x}  sp {}  stack [{}]\ndt  {}  st {}  cycles {}  frames {}\n",
+            self.pc, op, v.join(" "), self.i, self.sp, stack.join(" "), self.delay, self.sound, self.cycles, self.frames,
+        )
+    }
+
+    // halted by fx0a until a key is pressed
+    pub fn waiting(&self) -> bool {
+        self.kwait
+    }
+
+    // one 60 Hz frame: a batch of machine cycles, then a timer tick.
+    // stops early before an instruction with a breakpoint;
+    // the next call executes it and finishes the frame
+    pub fn frame(&mut self, keypad: &[bool; 16]) -> Result<(), Error> {
+        while self.fcycle < self.ipf {
+            if self.hit.take().is_none() && !self.kwait && self.breakpoints.contains(&self.pc) {
+                self.hit = Some(self.pc);
+                return Ok(());
+            }
+            self.mcycle(keypad)?;
+            self.fcycle += 1;
+        }
+        self.fcycle = 0;
+        self.tick();
+        Ok(())
+    }
+
+    // a single machine cycle, ticking the timers at the end of a frame
+    pub fn step(&mut self, keypad: &[bool; 16]) -> Result<(), Error> {
+        self.mcycle(keypad)?;
+        self.fcycle += 1;
+        if self.fcycle == self.ipf {
+            self.fcycle = 0;
+            self.tick();
+        }
+        // stepping onto a breakpoint doesn't stop the next frame there again
+        self.hit = Some(self.pc).filter(|pc| self.breakpoints.contains(pc));
+        Ok(())
+    }
+
+    // decrement timers
+    fn tick(&mut self) {
+        self.frames += 1;
+        if let Some(p) = &mut self.profile { p.frame(); }
+        if self.delay > 0 {
+            self.delay -= 1;
+        }
+        if self.sound > 0 {
+            self.sound -= 1;
+        }
+    }
+
     // one machine cycle
     pub fn mcycle(&mut self, keypad: &[bool; 16]) -> Result<(), Error> {
         self.keyb = *keypad; // read the state of the keypad
+        self.cycles += 1;
 
         // halted state, wait for keypress
         if self.kwait {
-            for ii in 0..self.keyb.len() {
-                if keypad[ii] {
+            if let Some(p) = &mut self.profile { p.wait(); }
+            for (ii, pressed) in keypad.iter().enumerate() {
+                if *pressed {
                     self.kwait = false;
                     self.v[self.kreg] = ii as u8;
                 }
             }
         } else {
-            // decrement timers
-            if self.delay > 0 {
-                print!("\x07"); // BEEP
-                self.delay -= 1;
-            }
-            if self.sound > 0 {
-                self.sound -= 1;
-            }
             self.icycle()?;
         }
         Ok(())
     }
 
-    fn debug_print(&self, s: &str) {
-        if self.verbose { println!("{}", s); }
+    pub fn set_tracer(&mut self, t: Tracer) {
+        self.tracer = Some(t);
+    }
+
+    // write out a ring buffer trace after a crash
+    pub fn dump_trace(&mut self) -> Result<(), Error> {
+        match &mut self.tracer {
+            Some(t) => t.dump(),
+            None    => Ok(()),
+        }
+    }
+
+    // start gathering execution statistics
+    pub fn enable_profile(&mut self) {
+        self.profile = Some(Profile::new());
+    }
+
+    pub fn profile_report(&self) -> Option<String> {
+        self.profile.as_ref().map(|p| p.report(&self.ram))
+    }
+
+    // start tracking which bytes of the program are used
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(Coverage::new());
+    }
+
+    pub fn coverage_report(&self, format: coverage::Format, name: &str) -> Option<String> {
+        let len = self.prog.as_ref().map_or(0, |r| r.len());
+        self.coverage.as_ref().map(|cv| cv.report(&self.ram, len, format, name))
+    }
+
+    fn regs(&self) -> Regs {
+        Regs { v: self.v, i: self.i, sp: self.sp }
     }
 
     // one instruction cycle (variable machine cycle)
     // super handy specification:
     // http://johnearnest.github.io/Octo/docs/chip8ref.pdf
     fn icycle(&mut self) -> Result<(), Error> {
+        if self.pc + 1 >= RAM_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, format!("pc {:#05x} is past the end of RAM!", self.pc)));
+        }
         let op = self.fetch();
-        if self.verbose { println!("fetch [{:#08x}] {:#06x}", self.pc, op); }
-        // split 2-byte opcode into 4 nibbles
-        let nibs = (
-            (op & 0xf000) >> 12 as u8,
-            (op & 0x0f00) >> 8  as u8,
-            (op & 0x00f0) >> 4  as u8,
-            (op & 0x000f) >> 0  as u8,
-        );
-        let nnn = (op & 0x0fff) as usize; // address
-        let nn  = (op & 0x00ff) as u8;    // 8-bit constant
-        let x   = nibs.1 as usize;        // 4-bit constant
-        let y   = nibs.2 as usize;        // 4-bit constant
-        let n   = nibs.3 as usize;        // opcode argument
-
-        let cycle_count: PC = match nibs {
-            (0x0, 0x0, 0xe, 0x0) => { self.debug_print("clear");      self.op_00e0()        },
-            (0x0, 0x0, 0xe, 0xe) => { self.debug_print("return");     self.op_00ee()        },
-            (0x1, _,   _,   _  ) => { self.debug_print("jmp nnn");    self.op_1nnn(nnn)     },
-            (0x2, _,   _,   _  ) => { self.debug_print("call nnn");   self.op_2nnn(nnn)     },
-            (0x3, _,   _,   _  ) => { self.debug_print("if vx!=nn");  self.op_3xnn(x, nn)   },
-            (0x4, _,   _,   _  ) => { self.debug_print("if vx==nn");  self.op_4xnn(x, nn)   },
-            (0x5, _,   _,   _  ) => { self.debug_print("if vx!=vy");  self.op_5xy0(x, y)    },
-            (0x6, _,   _,   _  ) => { self.debug_print("vx = nn");    self.op_6xnn(x, nn)   },
-            (0x7, _,   _,   _  ) => { self.debug_print("vx += nn");   self.op_7xnn(x, nn)   },
-            (0x8, _,   _,   0x0) => { self.debug_print("vx = vy");    self.op_8xy0(x, y)    },
-            (0x8, _,   _,   0x1) => { self.debug_print("vx |= vy");   self.op_8xy1(x, y)    },
-            (0x8, _,   _,   0x2) => { self.debug_print("vx &= vy");   self.op_8xy2(x, y)    },
-            (0x8, _,   _,   0x3) => { self.debug_print("vx ^= vy");   self.op_8xy3(x, y)    },
-            (0x8, _,   _,   0x4) => { self.debug_print("vx += vy");   self.op_8xy4(x, y)    },
-            (0x8, _,   _,   0x5) => { self.debug_print("vx -= vy");   self.op_8xy5(x, y)    },
-            (0x8, _,   _,   0x6) => { self.debug_print("vx >>= vy");  self.op_8xy6(x, y)    },
-            (0x8, _,   _,   0x7) => { self.debug_print("vx = -vy");   self.op_8xy7(x, y)    },
-            (0x8, _,   _,   0xe) => { self.debug_print("vx <<= vy");  self.op_8xye(x, y)    },
-            (0x9, _,   _,   0x0) => { self.debug_print("vx == vy");   self.op_9xy0(x, y)    },
-            (0xa, _,   _,   _  ) => { self.debug_print("i = nnn");    self.op_annn(nnn)     },
-            (0xb, _,   _,   _  ) => { self.debug_print("jmp nnn+v0"); self.op_bnnn(nnn)     },
-            (0xc, _,   _,   _  ) => { self.debug_print("vx = rand");  self.op_cxnn(x, nn)   },
-            (0xd, _,   _,   _  ) => { self.debug_print("sp vx vy n"); self.op_dxyn(x, y, n) },
-            (0xe, _,   0x9, 0xe) => { self.debug_print("!key");       self.op_ex9e(x)       },
-            (0xe, _,   0xa, 0x1) => { self.debug_print("key");        self.op_exa1(x)       },
-            (0xf, _,   0x0, 0x7) => { self.debug_print("vx = delay"); self.op_fx07(x)       },
-            (0xf, _,   0x0, 0xa) => { self.debug_print("vx = key");   self.op_fx0a(x)       },
-            (0xf, _,   0x1, 0x5) => { self.debug_print("delay = vx"); self.op_fx15(x)       },
-            (0xf, _,   0x1, 0x8) => { self.debug_print("buzz = vx");  self.op_fx18(x)       },
-            (0xf, _,   0x1, 0xe) => { self.debug_print("i += vx");    self.op_fx1e(x)       },
-            (0xf, _,   0x2, 0x9) => { self.debug_print("i = hx(vx)"); self.op_fx29(x)       },
-            (0xf, _,   0x3, 0x3) => { self.debug_print("bcd vx");     self.op_fx33(x)       },
-            (0xf, _,   0x5, 0x5) => { self.debug_print("save vx");    self.op_fx55(x)       },
-            (0xf, _,   0x6, 0x5) => { self.debug_print("load vx");    self.op_fx65(x)       },
-            (_,   _,   _,   _  ) => {
-                return Err(Error::new(
-                    ErrorKind::InvalidData, format!("unexpected opcode {:#02x}!", op)
-                ));
-            },
+        let pc = self.pc;
+        let before = self.regs();
+        let inst = Instruction::decode(op);
+        let res = match inst {
+            Some(inst) => self.execute(inst),
+            None => Err(Error::new(
+                ErrorKind::InvalidData, format!("unexpected opcode {:#02x}!", op)
+            )),
+        };
+        if let (Some(p), Some(inst)) = (&mut self.profile, inst) {
+            p.instruction(pc, &inst, self.pc);
+        }
+        if let (Some(cv), Some(_)) = (&mut self.coverage, inst) {
+            cv.execute(pc);
+        }
+        if self.tracer.is_some() {
+            let writes = std::mem::take(&mut self.writes);
+            let r = Record { cycle: self.cycles, pc, op, inst, before, after: self.regs(), writes };
+            if let Some(t) = &mut self.tracer { t.record(r)?; }
+        }
+        res
+    }
+
+    fn execute(&mut self, inst: Instruction) -> Result<(), Error> {
+        use Instruction::*;
+        self.check(inst)?;
+        let cycle_count: PC = match inst {
+            Clear               => self.op_00e0(),
+            Return              => self.op_00ee(),
+            Jump(nnn)           => self.op_1nnn(nnn),
+            Call(nnn)           => self.op_2nnn(nnn),
+            SkipEq(x, nn)       => self.op_3xnn(x, nn),
+            SkipNe(x, nn)       => self.op_4xnn(x, nn),
+            SkipEqReg(x, y)     => self.op_5xy0(x, y),
+            Load(x, nn)         => self.op_6xnn(x, nn),
+            Add(x, nn)          => self.op_7xnn(x, nn),
+            Move(x, y)          => self.op_8xy0(x, y),
+            Or(x, y)            => self.op_8xy1(x, y),
+            And(x, y)           => self.op_8xy2(x, y),
+            Xor(x, y)           => self.op_8xy3(x, y),
+            AddReg(x, y)        => self.op_8xy4(x, y),
+            Sub(x, y)           => self.op_8xy5(x, y),
+            ShiftRight(x, y)    => self.op_8xy6(x, y),
+            SubFrom(x, y)       => self.op_8xy7(x, y),
+            ShiftLeft(x, y)     => self.op_8xye(x, y),
+            SkipNeReg(x, y)     => self.op_9xy0(x, y),
+            LoadI(nnn)          => self.op_annn(nnn),
+            JumpV0(nnn)         => self.op_bnnn(nnn),
+            Random(x, nn)       => self.op_cxnn(x, nn),
+            Draw(x, y, n)       => self.op_dxyn(x, y, n),
+            SkipKey(x)          => self.op_ex9e(x),
+            SkipNoKey(x)        => self.op_exa1(x),
+            GetDelay(x)         => self.op_fx07(x),
+            WaitKey(x)          => self.op_fx0a(x),
+            SetDelay(x)         => self.op_fx15(x),
+            SetSound(x)         => self.op_fx18(x),
+            AddI(x)             => self.op_fx1e(x),
+            Font(x)             => self.op_fx29(x),
+            Bcd(x)              => self.op_fx33(x),
+            Save(x)             => self.op_fx55(x),
+            Restore(x)          => self.op_fx65(x),
         };
 
         match cycle_count {
-            PC::I    => Ok(self.pc = self.pc.wrapping_add(1*OP_LEN)),
-            PC::C    => Ok(self.pc = self.pc.wrapping_add(2*OP_LEN)),
-            PC::J(a) => Ok(self.pc = a),
+            PC::I    => self.pc = self.pc.wrapping_add(OP_LEN),
+            PC::C    => self.pc = self.pc.wrapping_add(2*OP_LEN),
+            PC::J(a) => self.pc = a,
         }
+        Ok(())
+    }
+
+    // instructions that would reach past the end of RAM or the stack
+    fn check(&self, inst: Instruction) -> Result<(), Error> {
+        use Instruction::*;
+        let end = match inst {
+            Call(_) if self.sp + 1 >= STACK_SIZE => {
+                return Err(Error::new(ErrorKind::InvalidData, "stack overflow!"));
+            },
+            Draw(_, _, n)           => self.i + n,
+            Bcd(_)                  => self.i + 3,
+            Save(x) | Restore(x)    => self.i + x + 1,
+            _                       => return Ok(()),
+        };
+        if end > RAM_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidData, format!("{} at i = {:#05x} runs past the end of RAM!", inst.kind(), self.i)
+            ));
+        }
+        Ok(())
     }
 
     // clear
@@ -343,8 +693,7 @@ impl Cpu {
     // return
     fn op_00ee(&mut self) -> PC {
         let rl = self.s[self.sp];
-        self.pc -= 1;
-        if self.verbose { println!("stack: {:#x?}", self.s); }
+        self.sp = self.sp.saturating_sub(1);
         PC::J(rl)
     }
 
@@ -357,7 +706,6 @@ impl Cpu {
     fn op_2nnn(&mut self, nnn: usize) -> PC {
         self.sp += 1;
         self.s[self.sp] = self.pc + OP_LEN;
-        if self.verbose { println!("stack: {:#x?}", self.s); }
         PC::J(nnn)
     }
 
@@ -385,7 +733,7 @@ impl Cpu {
     // v[x] += nn
     fn op_7xnn(&mut self, x: usize, nn: u8) -> PC {
         let res = self.v[x].wrapping_add(nn);
-        self.v[x] = res as u8;
+        self.v[x] = res;
         PC::I
     }
 
@@ -398,63 +746,68 @@ impl Cpu {
     // v[x] |= v[y]
     fn op_8xy1(&mut self, x: usize, y: usize) -> PC {
         self.v[x] |= self.v[y];
+        if self.quirks.vf_reset { self.v[0xf] = 0; }
         PC::I
     }
 
     // v[x] &= v[y]
     fn op_8xy2(&mut self, x: usize, y: usize) -> PC {
         self.v[x] &= self.v[y];
+        if self.quirks.vf_reset { self.v[0xf] = 0; }
         PC::I
     }
 
     // v[x] ^= v[y]
     fn op_8xy3(&mut self, x: usize, y: usize) -> PC {
         self.v[x] ^= self.v[y];
+        if self.quirks.vf_reset { self.v[0xf] = 0; }
         PC::I
     }
 
     // v[x] += v[y]
     fn op_8xy4(&mut self, x: usize, y: usize) -> PC {
-        let res = self.v[x].wrapping_add(self.v[y]);
-        // flag set on addition overflow
-        self.v[0xf] = if self.v[x] ^ res == 0b1000_0000 { 1 } else { 0 };
-        self.v[x] = res as u8;
+        let (res, carry) = self.v[x].overflowing_add(self.v[y]);
+        self.v[x] = res;
+        // flag set on addition overflow, written last so it wins when x == 0xf
+        self.v[0xf] = carry as u8;
         PC::I
     }
 
     // v[x] -= v[y]
     fn op_8xy5(&mut self, x: usize, y: usize) -> PC {
-        let res = self.v[x].wrapping_sub(self.v[y]);
-        // flag set on subtraction underflow
-        self.v[0xf] = if self.v[x] ^ res == 0b1000_0000 { 1 } else { 0 };
-        self.v[x] = res as u8;
+        let (res, borrow) = self.v[x].overflowing_sub(self.v[y]);
+        self.v[x] = res;
+        // flag cleared on subtraction underflow
+        self.v[0xf] = !borrow as u8;
         PC::I
     }
 
     // v[x] >>= v[y]
-    fn op_8xy6(&mut self, x: usize, _y: usize) -> PC {
-        let res = (self.v[x] as u16) / 2;
-        self.v[x] = res as u8; // does this underflow?
+    fn op_8xy6(&mut self, x: usize, y: usize) -> PC {
+        if !self.quirks.shifting { self.v[x] = self.v[y]; }
+        let lsb = self.v[x] & 0b0000_0001;
+        self.v[x] >>= 1;
         // flag set old LSB
-        self.v[0xf] = if self.v[x] & 0b0000_0001 == 0b0000_0001 { 1 } else { 0 };
+        self.v[0xf] = lsb;
         PC::I
     }
 
     // v[x] = (v[y] - v[x])
     fn op_8xy7(&mut self, x: usize, y: usize) -> PC {
-        let res = self.v[y].wrapping_sub(self.v[x]);
-        // flag set on subtraction underflow
-        self.v[0xf] = if self.v[x] ^ res == 0b1000_0000 { 1 } else { 0 };
-        self.v[x] = res as u8;
-        return PC::I
+        let (res, borrow) = self.v[y].overflowing_sub(self.v[x]);
+        self.v[x] = res;
+        // flag cleared on subtraction underflow
+        self.v[0xf] = !borrow as u8;
+        PC::I
     }
 
     // v[x] <<= v[y]
-    fn op_8xye(&mut self, x: usize, _y: usize) -> PC {
-        let res = (self.v[x] as u16) * 2;
+    fn op_8xye(&mut self, x: usize, y: usize) -> PC {
+        if !self.quirks.shifting { self.v[x] = self.v[y]; }
+        let msb = self.v[x] >> 7;
+        self.v[x] <<= 1;
         // flag set old MSB
-        self.v[0xf] = if self.v[x] & 0b1000_0000 == 0b1000_0000 { 1 } else { 0 };
-        self.v[x] = res as u8; // does this overflow?
+        self.v[0xf] = msb;
         PC::I
     }
 
@@ -471,27 +824,39 @@ impl Cpu {
 
     // pc = nnn + v[0]
     fn op_bnnn(&mut self, nnn: usize) -> PC {
-        PC::J(nnn + self.v[0] as usize)
+        let x = if self.quirks.jumping { nnn >> 8 } else { 0 };
+        PC::J(nnn + self.v[x] as usize)
     }
 
     // v[x] = rand(255) & nn
     fn op_cxnn(&mut self, x: usize, nn: u8) -> PC {
-        // two approaches:
-        // 0: r = rand() % nn
-        // 1: r = (rand() % 255) & nn
-        // both produce r in [0, nn], but distrubtion of (1) follows instruction set
-        self.v[x] = (rand::random::<u8>() % 255) & nn;
+        // masking a uniform byte (rather than rand() % nn) follows the instruction set;
+        // the full 0..=255 range is used, so every bit of nn can be set
+        self.v[x] = self.rng.byte() & nn;
         PC::I
     }
 
     // sprite v[x] v[y] n
     fn op_dxyn(&mut self, x: usize, y: usize, n: usize) -> PC {
         self.v[0xf] = 0; // change flag
+        self.sprite.0 = self.i;
+        self.sprite.1.clear();
+        // clipped sprites wrap their position, but not their pixels
+        let clip = self.quirks.clipping;
+        let (x0, y0) = if clip {
+            (self.v[x] as usize % V_WIDTH, self.v[y] as usize % V_HEIGHT)
+        } else {
+            (self.v[x] as usize, self.v[y] as usize)
+        };
         for byte_number in 0..n {
-            let sprite_y = (self.v[y] as usize + byte_number) % V_HEIGHT;
+            let row = self.load(self.i + byte_number);
+            self.sprite.1.push(row);
+            if clip && y0 + byte_number >= V_HEIGHT { continue; }
+            let sprite_y = (y0 + byte_number) % V_HEIGHT;
             for bit in 0..8 {
-                let sprite_x = (self.v[x] as usize + bit) % V_WIDTH;
-                let c = (self.ram[self.i + byte_number] >> (7 - bit)) & 0x1;
+                if clip && x0 + bit >= V_WIDTH { break; }
+                let sprite_x = (x0 + bit) % V_WIDTH;
+                let c = (row >> (7 - bit)) & 0x1;
                 self.v[0xf] |= c & self.vram[sprite_x][sprite_y]; // flag set if bit cleared
                 self.vram[sprite_x][sprite_y] ^= c;
             }
@@ -501,12 +866,13 @@ impl Cpu {
 
     // if v[x] == key then
     fn op_ex9e(&mut self, x: usize) -> PC {
-        PC::cond(self.keyb[self.v[x] as usize])
+        // only the low nybble picks the key, as on the VIP
+        PC::cond(self.keyb[(self.v[x] & 0xf) as usize])
     }
 
     // if v[x] != key then
     fn op_exa1(&mut self, x: usize) -> PC {
-        PC::cond(!self.keyb[self.v[x] as usize])
+        PC::cond(!self.keyb[(self.v[x] & 0xf) as usize])
     }
 
     // v[x] = delay
@@ -536,8 +902,7 @@ impl Cpu {
 
     // i += v[x]
     fn op_fx1e(&mut self, x: usize) -> PC {
-        let res = self.v[x].wrapping_add(self.i as u8);
-        self.i = res as usize;
+        self.i = (self.i + self.v[x] as usize) & (RAM_SIZE - 1);
         PC::I
     }
 
@@ -551,25 +916,27 @@ impl Cpu {
 
     // bcd(v[x])
     fn op_fx33(&mut self, x: usize) -> PC {
-        self.ram[self.i    ] = self.v[x] / 100;             // hundreds place
-        self.ram[self.i + 1] = (self.v[x] % 100) / 10;      // tens place
-        self.ram[self.i + 2] = self.v[x] % 10;              // ones place
+        self.store(self.i,     self.v[x] / 100);            // hundreds place
+        self.store(self.i + 1, (self.v[x] % 100) / 10);     // tens place
+        self.store(self.i + 2, self.v[x] % 10);             // ones place
         PC::I
     }
 
     // store v
     fn op_fx55(&mut self, x: usize) -> PC {
         for ii in 0..x+1 {
-            self.ram[self.i + ii] = self.v[ii];
+            self.store(self.i + ii, self.v[ii]);
         }
+        if self.quirks.memory { self.i = (self.i + x + 1) & (RAM_SIZE - 1); }
         PC::I
     }
 
     // load v
     fn op_fx65(&mut self, x: usize) -> PC {
         for ii in 0..x+1 {
-            self.v[ii] = self.ram[self.i + ii];
+            self.v[ii] = self.load(self.i + ii);
         }
+        if self.quirks.memory { self.i = (self.i + x + 1) & (RAM_SIZE - 1); }
         PC::I
     }
 }
@@ -0,0 +1,647 @@
+use std::{
+    collections::BTreeSet,
+    io::Error,
+};
+
+use termion::event::Key;
+use tui::{
+    Frame,
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    cheat::{Cheats, Filter, Search},
+    cpu::{Cpu, PC_BASE, RAM_SIZE},
+    instruction::Instruction,
+};
+
+// frames a write stays highlighted in the memory view
+const RECENT: u64 = 30;
+
+// call stack entries shown below the disassembly
+const STACK_ROWS: usize = 8;
+
+// search results are listed once there are this few
+const CANDIDATES: usize = 64;
+
+// debugger panes shown beside the display, toggled with F1.
+// while open, the debugger takes the keys it knows and passes the rest to the keypad
+pub struct Debugger {
+    pub open:   bool,
+    pub paused: bool,
+    pane:       Pane,       // pane shown, Tab to switch
+    dis:        Disasm,
+    mem:        Memory,
+    spr:        Sprites,
+    cht:        CheatSearch,
+    cut:        Option<[bool; 16]>, // keys of the frame a breakpoint stopped part way
+    locked:     bool,           // recording a movie, which only holds key presses
+    message:    Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pane {
+    Disasm,
+    Memory,
+    Sprites,
+    Cheats,
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger {
+            open:   false,
+            paused: false,
+            pane:   Pane::Disasm,
+            dis:    Disasm::new(),
+            mem:    Memory::new(),
+            spr:    Sprites::new(),
+            cht:    CheatSearch::new(Cheats::default()),
+            cut:    None,
+            locked: false,
+            message: None,
+        }
+    }
+
+    // refuse changes to the machine a movie couldn't replay
+    pub fn locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    // cheats loaded for the ROM, frozen every frame
+    pub fn cheats(mut self, cheats: Cheats) -> Self {
+        self.cht = CheatSearch::new(cheats);
+        self
+    }
+
+    // run a frame with the keys held, pausing at a breakpoint. a frame cut
+    // short is finished with the keys it started with, so it runs as it
+    // would have without stopping; returns its keys once it's complete
+    pub fn frame(&mut self, c: &mut Cpu, key: &[bool; 16]) -> Result<Option<[bool; 16]>, Error> {
+        // a reset or a loaded state starts the frame afresh
+        let key = self.cut.take().filter(|_| c.mid_frame()).unwrap_or(*key);
+        c.frame(&key)?;
+        if c.at_breakpoint().is_some() {
+            self.cut = Some(key);
+            self.paused = true;
+            self.open = true;
+            return Ok(None);
+        }
+        self.cht.cheats.apply(c);
+        Ok(Some(key))
+    }
+
+    // after the machine is reset, cheats are applied as on load
+    pub fn reset(&mut self, c: &mut Cpu) {
+        self.cht.cheats.apply_all(c);
+        self.mem.nibble = None;
+    }
+
+    // half way through typing a byte, where Esc cancels it
+    pub fn editing(&self) -> bool {
+        self.open && (self.mem.nibble.is_some() || self.cht.entry.is_some())
+    }
+
+    // handle a key, returning false if it should go to the keypad instead
+    pub fn key(&mut self, key: Key, c: &mut Cpu) -> Result<bool, Error> {
+        self.message = None;
+        match key {
+            Key::F(1)       => self.open = !self.open,
+            Key::Char('p')  => {
+                self.paused = !self.paused;
+                self.mem.nibble = None;
+            },
+            _ if !self.open => return Ok(false),
+            Key::Char('\t') => {
+                self.pane = match self.pane {
+                    Pane::Disasm  => Pane::Memory,
+                    Pane::Memory  => Pane::Sprites,
+                    Pane::Sprites => Pane::Cheats,
+                    Pane::Cheats  => Pane::Disasm,
+                };
+                self.mem.nibble = None;
+            },
+            _ if self.locked && self.edits(key) => self.message = Some(String::from("not while recording a movie")),
+            _ => return match self.pane {
+                Pane::Disasm  => self.dis.key(key, c, self.paused),
+                Pane::Memory  => Ok(self.mem.key(key, c, self.paused)),
+                Pane::Sprites => Ok(self.spr.key(key, c)),
+                Pane::Cheats  => self.cht.key(key, c),
+            },
+        }
+        Ok(true)
+    }
+
+    // keys that change the machine: stepping, editing memory and cheats
+    fn edits(&self, key: Key) -> bool {
+        match self.pane {
+            Pane::Disasm  => self.paused && key == Key::Char('n'),
+            Pane::Memory  => self.paused && matches!(key, Key::Char(ch) if ch.is_ascii_hexdigit()),
+            Pane::Sprites => false,
+            Pane::Cheats  => self.cht.entry.is_none()
+                && matches!(key, Key::Char(':') | Key::Char('\n') | Key::Delete | Key::Backspace),
+        }
+    }
+
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, c: &Cpu) {
+        // a refused key says why on the last line
+        let area = match &self.message {
+            None    => area,
+            Some(m) => {
+                let line = Rect { y: area.bottom().saturating_sub(1), height: area.height.min(1), ..area };
+                f.render_widget(Paragraph::new(Span::styled(m.clone(), Style::default().fg(Color::Yellow))), line);
+                Rect { height: area.height.saturating_sub(1), ..area }
+            },
+        };
+        match self.pane {
+            Pane::Disasm  => self.dis.draw(f, area, c, self.paused),
+            Pane::Memory  => self.mem.draw(f, area, c, self.paused),
+            Pane::Sprites => self.spr.draw(f, area, c),
+            Pane::Cheats  => self.cht.draw(f, area, c),
+        }
+    }
+}
+
+// instruction at addr, if it decodes
+fn decode(c: &Cpu, addr: usize) -> (u16, Option<Instruction>) {
+    let ram = c.ram();
+    let op = (ram[addr % RAM_SIZE] as u16) << 8 | ram[(addr + 1) % RAM_SIZE] as u16;
+    (op, Instruction::decode(op))
+}
+
+// disassembly around PC with breakpoints, and the call stack
+struct Disasm {
+    cursor: Option<usize>,  // selected address, None to follow PC
+}
+
+impl Disasm {
+    fn new() -> Self {
+        Disasm { cursor: None }
+    }
+
+    fn key(&mut self, key: Key, c: &mut Cpu, paused: bool) -> Result<bool, Error> {
+        let at = self.cursor.unwrap_or(c.pc());
+        let line = |n: isize| Some((at as isize + 2 * n).rem_euclid(RAM_SIZE as isize) as usize);
+        match key {
+            Key::Up         => self.cursor = line(-1),
+            Key::Down       => self.cursor = line(1),
+            Key::PageUp     => self.cursor = line(-16),
+            Key::PageDown   => self.cursor = line(16),
+            Key::Char('g')  => self.cursor = None,
+            Key::Char('b') | Key::Char('\n') => { c.toggle_breakpoint(at); },
+            // single step while paused
+            Key::Char('n') if paused => {
+                c.step(&[false; 16])?;
+                self.cursor = None;
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, c: &Cpu, paused: bool) {
+        let stack = c.stack();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(stack.len().min(STACK_ROWS) as u16 + 2)].as_ref())
+            .split(area);
+
+        // lines centred on the selection, keeping its alignment
+        let rows = chunks[0].height.saturating_sub(2) as usize;
+        let at = self.cursor.unwrap_or(c.pc());
+        let start = at - (rows / 2).min(at / 2) * 2;
+        let addrs: Vec<usize> = (start..RAM_SIZE - 1).step_by(2).take(rows).collect();
+        let listing = self.listing(c, &addrs);
+
+        let title = match (paused, c.at_breakpoint()) {
+            (true, Some(a)) => format!("disassembly (breakpoint {:03x}, n to step)", a),
+            (true, None)    => String::from("disassembly (paused, n to step)"),
+            (false, _)      => String::from("disassembly"),
+        };
+        let p = Paragraph::new(listing).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(p, chunks[0]);
+
+        // innermost call first, shown as the call site
+        let calls: Vec<Spans> = stack.iter().rev().take(STACK_ROWS)
+            .map(|ret| {
+                let site = ret.wrapping_sub(2);
+                let inst = decode(c, site).1.map(|i| i.to_string()).unwrap_or_default();
+                Spans::from(format!("{:03x}  {}", site, inst))
+            })
+            .collect();
+        let title = format!("call stack ({})", stack.len());
+        let p = Paragraph::new(calls).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(p, chunks[1]);
+    }
+
+    // one line per address: breakpoint, PC and jump target markers, then the instruction
+    fn listing(&self, c: &Cpu, addrs: &[usize]) -> Vec<Spans<'static>> {
+        let insts: Vec<(u16, Option<Instruction>)> = addrs.iter().map(|a| decode(c, *a)).collect();
+        let targets: BTreeSet<usize> = insts.iter()
+            .filter_map(|(_, i)| match i {
+                Some(Instruction::Jump(a)) | Some(Instruction::Call(a)) | Some(Instruction::JumpV0(a)) => Some(*a),
+                _ => None,
+            })
+            .collect();
+
+        addrs.iter().zip(insts).map(|(a, (op, inst))| {
+            let brk = c.breakpoints().contains(a);
+            let text = format!(
+                "{}{}{} {:03x}  {:04x}  {}",
+                if brk { '●' } else { ' ' },
+                if *a == c.pc() { '▶' } else { ' ' },
+                if targets.contains(a) { '»' } else { ' ' },
+                a, op,
+                inst.map(|i| i.to_string()).unwrap_or_default(),
+            );
+            let mut s = Style::default();
+            if *a == c.pc() { s = s.fg(Color::Black).bg(Color::Yellow); }
+            else if brk { s = s.fg(Color::Red); }
+            if Some(*a) == self.cursor { s = s.add_modifier(Modifier::REVERSED); }
+            Spans::from(Span::styled(text, s))
+        }).collect()
+    }
+}
+
+// scrollable hex dump of RAM, editable while paused
+struct Memory {
+    cursor: usize,          // selected address
+    top:    usize,          // address of the first row shown
+    per:    usize,          // bytes per row, set by the width of the pane
+    nibble: Option<u8>,     // high nibble typed so far
+}
+
+impl Memory {
+    fn new() -> Self {
+        Memory {
+            cursor: PC_BASE,
+            top:    PC_BASE,
+            per:    16,
+            nibble: None,
+        }
+    }
+
+    fn goto(&mut self, addr: usize) {
+        self.cursor = addr % RAM_SIZE;
+        self.nibble = None;
+    }
+
+    fn step(&mut self, by: isize) {
+        self.goto((self.cursor as isize + by).rem_euclid(RAM_SIZE as isize) as usize);
+    }
+
+    fn key(&mut self, key: Key, c: &mut Cpu, paused: bool) -> bool {
+        let per = self.per as isize;
+        match key {
+            Key::Left       => self.step(-1),
+            Key::Right      => self.step(1),
+            Key::Up         => self.step(-per),
+            Key::Down       => self.step(per),
+            Key::PageUp     => self.step(-per * 16),
+            Key::PageDown   => self.step(per * 16),
+            Key::Char('g')  => self.goto(c.pc()),
+            Key::Char('i')  => self.goto(c.index()),
+            // typing two hex digits writes a byte and moves on
+            Key::Char(ch) if paused && ch.is_ascii_hexdigit() => {
+                let d = ch.to_digit(16).unwrap() as u8; // safe unwrap: checked above
+                match self.nibble.take() {
+                    None     => self.nibble = Some(d),
+                    Some(hi) => {
+                        c.poke(self.cursor, hi << 4 | d);
+                        self.step(1);
+                    },
+                }
+            },
+            Key::Esc if self.nibble.is_some() => self.nibble = None,
+            _ => return false,
+        }
+        true
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, c: &Cpu, paused: bool) {
+        // "addr " + " xx" per byte + "  " + ascii per byte, inside the borders
+        let width = area.width.saturating_sub(2) as usize;
+        self.per = if width >= 4 + 16 * 4 + 2 { 16 } else { 8 };
+        let rows = (area.height.saturating_sub(2) as usize).clamp(1, RAM_SIZE / self.per);
+
+        // scroll to keep the cursor in view
+        let row = self.cursor / self.per * self.per;
+        self.top = self.top / self.per * self.per;
+        if row < self.top { self.top = row; }
+        if row >= self.top + rows * self.per { self.top = row + self.per - rows * self.per; }
+        // a taller or narrower pane can't show past the end of RAM
+        self.top = self.top.min(RAM_SIZE.saturating_sub(rows * self.per));
+
+        let lines: Vec<Spans> = (0..rows)
+            .map(|r| self.row(c, self.top + r * self.per, paused))
+            .collect();
+        let title = format!("memory {:03x}{}", self.cursor, if paused { " (paused, 0-f to edit)" } else { "" });
+        let p = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(p, area);
+    }
+
+    fn row(&self, c: &Cpu, addr: usize, paused: bool) -> Spans<'static> {
+        let bytes = c.ram()[addr..addr + self.per].iter().zip(addr..);
+        let mut spans = vec![Span::raw(format!("{:03x} ", addr))];
+        for (b, a) in bytes.clone() {
+            let text = match (a == self.cursor, self.nibble) {
+                (true, Some(hi)) if paused => format!("{:x}_", hi),
+                _                          => format!("{:02x}", b),
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(text, self.style(c, a)));
+        }
+        spans.push(Span::raw("  "));
+        for (b, a) in bytes {
+            let ch = if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' };
+            spans.push(Span::styled(ch.to_string(), self.style(c, a)));
+        }
+        Spans::from(spans)
+    }
+
+    // cursor, then PC, I and recent writes
+    fn style(&self, c: &Cpu, a: usize) -> Style {
+        let mut s = Style::default();
+        if a == c.pc() || a == c.pc() + 1 {
+            s = s.fg(Color::Black).bg(Color::Yellow);
+        } else if a == c.index() {
+            s = s.fg(Color::Black).bg(Color::Cyan);
+        } else if c.written_ago(a).is_some_and(|n| n <= RECENT) {
+            s = s.fg(Color::Red).add_modifier(Modifier::BOLD);
+        }
+        if a == self.cursor { s = s.add_modifier(Modifier::REVERSED); }
+        s
+    }
+}
+
+// where the sprite viewer reads from
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Source {
+    Index,          // follow I
+    Addr(usize),    // a chosen address
+    Last,           // the rows drawn by the last dxyn
+}
+
+// RAM drawn as 8 pixel wide sprites
+struct Sprites {
+    source: Source,
+    height: usize,          // rows per sprite
+}
+
+impl Sprites {
+    fn new() -> Self {
+        Sprites { source: Source::Index, height: 5 }
+    }
+
+    fn base(&self, c: &Cpu) -> usize {
+        match self.source {
+            Source::Index   => c.index(),
+            Source::Addr(a) => a,
+            Source::Last    => c.last_sprite().0,
+        }
+    }
+
+    fn key(&mut self, key: Key, c: &Cpu) -> bool {
+        let h = self.height as isize;
+        let mut by = |n: isize| {
+            let a = (self.base(c) as isize + n).rem_euclid(RAM_SIZE as isize);
+            self.source = Source::Addr(a as usize);
+        };
+        match key {
+            Key::Left       => by(-1),
+            Key::Right      => by(1),
+            Key::Up         => by(-h),
+            Key::Down       => by(h),
+            Key::PageUp     => by(-h * 16),
+            Key::PageDown   => by(h * 16),
+            Key::Char('i')  => self.source = Source::Index,
+            Key::Char('l')  => self.source = Source::Last,
+            // the built-in font
+            Key::Char('t')  => {
+                self.source = Source::Addr(0);
+                self.height = 5;
+            },
+            Key::Char('+') | Key::Char('=') => self.height = (self.height + 1).min(15),
+            Key::Char('-')  => self.height = (self.height - 1).max(1),
+            _ => return false,
+        }
+        true
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, c: &Cpu) {
+        let (title, lines) = match self.source {
+            Source::Last => {
+                let (addr, rows) = c.last_sprite();
+                let title = format!("sprites: last drawn, {:03x} ({} rows)", addr, rows.len());
+                (title, half_blocks(rows).into_iter().map(Spans::from).collect())
+            },
+            _ => {
+                let base = self.base(c);
+                let title = format!(
+                    "sprites: {:03x}{}, {} rows",
+                    base, if self.source == Source::Index { " (I)" } else { "" }, self.height
+                );
+                (title, self.grid(c, base, area))
+            },
+        };
+        let p = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+        f.render_widget(p, area);
+    }
+
+    // as many sprites as fit, left to right, each under its address
+    fn grid(&self, c: &Cpu, base: usize, area: Rect) -> Vec<Spans<'static>> {
+        let cols = ((area.width.saturating_sub(2) as usize + 1) / 9).max(1);
+        let cell = 1 + self.height.div_ceil(2);
+        let rows = (area.height.saturating_sub(2) as usize / cell).max(1);
+
+        let mut lines = Vec::new();
+        for r in 0..rows {
+            let addrs: Vec<usize> = (0..cols).map(|k| (base + (r * cols + k) * self.height) % RAM_SIZE).collect();
+            let sprites: Vec<Vec<String>> = addrs.iter()
+                .map(|a| {
+                    let bytes: Vec<u8> = (*a..a + self.height).map(|b| c.ram()[b % RAM_SIZE]).collect();
+                    half_blocks(&bytes)
+                })
+                .collect();
+            lines.push(Spans::from(addrs.iter().map(|a| format!("{:03x}      ", a)).collect::<String>()));
+            for ii in 0..cell - 1 {
+                lines.push(Spans::from(sprites.iter().map(|s| format!("{} ", s[ii])).collect::<String>()));
+            }
+        }
+        lines
+    }
+}
+
+// what a typed hex value is for
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Entry {
+    Equal,      // search for the value
+    Set,        // the selected cheat's value
+}
+
+// RAM search and the list of cheats
+struct CheatSearch {
+    cheats: Cheats,
+    search: Option<Search>,
+    sel:    usize,                      // selected row: listed candidates, then cheats
+    entry:  Option<(Entry, Option<u8>)>, // value being typed, and its high nibble
+}
+
+impl CheatSearch {
+    fn new(cheats: Cheats) -> Self {
+        CheatSearch { cheats, search: None, sel: 0, entry: None }
+    }
+
+    // candidates small enough in number to list and select
+    fn listed(&self) -> &[usize] {
+        match &self.search {
+            Some(s) if s.candidates.len() <= CANDIDATES => &s.candidates,
+            _ => &[],
+        }
+    }
+
+    fn filter(&mut self, c: &Cpu, f: Filter) {
+        if let Some(s) = &mut self.search { s.filter(c.ram(), f); }
+        self.sel = 0;
+    }
+
+    fn key(&mut self, key: Key, c: &mut Cpu) -> Result<bool, Error> {
+        let rows = self.listed().len() + self.cheats.list.len();
+        // the selected cheat, if a cheat is selected
+        let cheat = self.sel.checked_sub(self.listed().len()).filter(|ii| *ii < self.cheats.list.len());
+
+        if let Some((what, hi)) = self.entry {
+            match key {
+                Key::Char(ch) if ch.is_ascii_hexdigit() => {
+                    let d = ch.to_digit(16).unwrap() as u8; // safe unwrap: checked above
+                    match hi {
+                        None     => self.entry = Some((what, Some(d))),
+                        Some(hi) => {
+                            self.entry = None;
+                            match (what, cheat) {
+                                (Entry::Equal, _)     => self.filter(c, Filter::Equal(hi << 4 | d)),
+                                (Entry::Set, Some(ii)) => {
+                                    let ch = &mut self.cheats.list[ii];
+                                    ch.value = hi << 4 | d;
+                                    c.poke(ch.addr, ch.value);
+                                    self.cheats.save()?;
+                                },
+                                (Entry::Set, None) => (),
+                            }
+                        },
+                    }
+                },
+                Key::Esc => self.entry = None,
+                _ => return Ok(false),
+            }
+            return Ok(true);
+        }
+
+        match key {
+            Key::Char('n')  => {
+                self.search = Some(Search::new(c.ram()));
+                self.sel = 0;
+            },
+            Key::Char('=')  => self.filter(c, Filter::Unchanged),
+            Key::Char('!')  => self.filter(c, Filter::Changed),
+            Key::Char('>')  => self.filter(c, Filter::Increased),
+            Key::Char('<')  => self.filter(c, Filter::Decreased),
+            Key::Char('#') if self.search.is_some() => self.entry = Some((Entry::Equal, None)),
+            Key::Char(':') if cheat.is_some()       => self.entry = Some((Entry::Set, None)),
+            Key::Up         => self.sel = self.sel.saturating_sub(1),
+            Key::Down       => self.sel = (self.sel + 1).min(rows.saturating_sub(1)),
+            // freeze a candidate at its current value, or toggle freezing a cheat
+            Key::Char('\n') => {
+                match cheat {
+                    Some(ii) => self.cheats.list[ii].freeze ^= true,
+                    None     => match self.listed().get(self.sel) {
+                        Some(a) => { let a = *a; self.cheats.add(a, c.ram()[a]); },
+                        None    => return Ok(true),
+                    },
+                }
+                self.cheats.save()?;
+            },
+            Key::Delete | Key::Backspace => if let Some(ii) = cheat {
+                self.cheats.list.remove(ii);
+                self.sel = self.sel.min((rows - 1).saturating_sub(1));
+                self.cheats.save()?;
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, c: &Cpu) {
+        let select = |ii: usize| if ii == self.sel { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        let value = match self.entry {
+            Some((_, Some(hi))) => format!("{:x}_", hi),
+            _                   => String::from("__"),
+        };
+
+        let mut lines = vec![Spans::from(match &self.search {
+            None    => String::from("no search: n to start"),
+            Some(s) => format!("{} candidates: = ! > < or # value", s.candidates.len()),
+        })];
+        if let Some((Entry::Equal, _)) = self.entry { lines.push(Spans::from(format!("search for {}", value))); }
+        for (ii, a) in self.listed().iter().enumerate() {
+            // safe unwrap: listed() is only non-empty during a search
+            let old = self.search.as_ref().unwrap().old(*a);
+            let text = format!("  {:03x}  {:02x} -> {:02x}", a, old, c.ram()[*a]);
+            lines.push(Spans::from(Span::styled(text, select(ii))));
+        }
+
+        lines.push(Spans::from(""));
+        lines.push(Spans::from("cheats: enter freezes, : sets, del removes"));
+        let first = self.listed().len();
+        for (ii, ch) in self.cheats.list.iter().enumerate() {
+            let v = match self.entry {
+                Some((Entry::Set, _)) if first + ii == self.sel => value.clone(),
+                _ => format!("{:02x}", ch.value),
+            };
+            let kind = if ch.freeze { "freeze" } else { "set   " };
+            let text = format!("  {} {:03x} {} {}", kind, ch.addr, v, ch.name);
+            lines.push(Spans::from(Span::styled(text, select(first + ii))));
+        }
+
+        // keep the selection in view
+        let sel = if self.sel < first { 1 + self.sel } else { 3 + self.sel };
+        let rows = area.height.saturating_sub(2) as usize;
+        let scroll = sel.saturating_sub(rows.saturating_sub(1)) as u16;
+        let p = Paragraph::new(lines)
+            .block(Block::default().title("cheats").borders(Borders::ALL))
+            .scroll((scroll, 0));
+        f.render_widget(p, area);
+    }
+}
+
+// sprite rows as text, two rows of pixels per line
+fn half_blocks(rows: &[u8]) -> Vec<String> {
+    rows.chunks(2)
+        .map(|pair| {
+            let (top, bottom) = (pair[0], pair.get(1).copied().unwrap_or(0));
+            (0..8).rev()
+                .map(|bit| match (top >> bit & 1, bottom >> bit & 1) {
+                    (1, 1) => '█',
+                    (1, 0) => '▀',
+                    (0, 1) => '▄',
+                    _      => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[path = "test/debugger_test.rs"]
+mod debugger_test;
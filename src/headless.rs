@@ -0,0 +1,150 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use crate::{
+    cpu::Cpu,
+    movie::Movie,
+    screen::{V_WIDTH, V_HEIGHT},
+};
+
+pub type Vram = [[u8; V_HEIGHT]; V_WIDTH];
+
+// run for a number of frames without a terminal, optionally feeding recorded input.
+// once the input runs out the keypad is left idle
+pub fn run(c: &mut Cpu, frames: u64, input: Option<&Movie>) -> Result<(), Error> {
+    let idle = [false; 16];
+    let mut keys = input.map(|m| m.frames());
+    for _ in 0..frames {
+        let key = keys.as_mut().and_then(|k| k.next()).unwrap_or(idle);
+        c.frame(&key)?;
+    }
+    Ok(())
+}
+
+// what the final framebuffer is compared against
+pub enum Expect {
+    Image(Box<Vram>),   // golden PBM image
+    Hash(u64),          // framebuffer hash, as printed by `hash`
+}
+
+impl Expect {
+    // a hash of 16 hex digits, as printed by `hash`, otherwise a path to a .pbm file
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let h = s.strip_prefix("0x").unwrap_or(s);
+        if h.len() == 16 && h.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            // safe unwrap: 16 hex digits always fit
+            return Ok(Expect::Hash(u64::from_str_radix(h, 16).unwrap()));
+        }
+        let data = fs::read(s).map_err(|e| Error::new(e.kind(), format!("{}: {}", s, e)))?;
+        Ok(Expect::Image(Box::new(pbm_read(&data)?)))
+    }
+
+    pub fn matches(&self, vram: &Vram) -> bool {
+        match self {
+            Expect::Image(img) => **img == *vram,
+            Expect::Hash(h)    => *h == hash(vram),
+        }
+    }
+}
+
+// 64-bit FNV-1a over the pixels, row by row; stable across runs and platforms
+pub fn hash(vram: &Vram) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for jj in 0..V_HEIGHT {
+        for col in vram.iter() {
+            h ^= col[jj] as u64;
+            h = h.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    h
+}
+
+// plain (P1) portable bitmap, 1 = pixel on
+pub fn pbm_write(vram: &Vram) -> String {
+    let mut s = format!("P1\n{} {}\n", V_WIDTH, V_HEIGHT);
+    for jj in 0..V_HEIGHT {
+        let row: Vec<&str> = vram.iter().map(|col| if col[jj] == 1 { "1" } else { "0" }).collect();
+        s.push_str(&row.join(" "));
+        s.push('\n');
+    }
+    s
+}
+
+// read a plain (P1) or raw (P4) portable bitmap the size of the screen
+pub fn pbm_read(data: &[u8]) -> Result<Vram, Error> {
+    let bad = |what: &str| Error::new(ErrorKind::InvalidData, format!("pbm: {}", what));
+
+    // header tokens, skipping comments
+    let mut pos = 0;
+    let mut token = || -> Option<String> {
+        loop {
+            while pos < data.len() && data[pos].is_ascii_whitespace() { pos += 1; }
+            if pos < data.len() && data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' { pos += 1; }
+                continue;
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() { pos += 1; }
+            return if start == pos { None } else { Some(String::from_utf8_lossy(&data[start..pos]).into()) };
+        }
+    };
+    let magic = token().ok_or_else(|| bad("empty file"))?;
+    let w: usize = token().and_then(|t| t.parse().ok()).ok_or_else(|| bad("bad width"))?;
+    let h: usize = token().and_then(|t| t.parse().ok()).ok_or_else(|| bad("bad height"))?;
+    if (w, h) != (V_WIDTH, V_HEIGHT) {
+        return Err(bad(&format!("image is {}x{}, screen is {}x{}", w, h, V_WIDTH, V_HEIGHT)));
+    }
+
+    let mut vram = [[0; V_HEIGHT]; V_WIDTH];
+    match magic.as_str() {
+        "P1" => {
+            let mut bits = data[pos..].iter()
+                .filter(|b| **b == b'0' || **b == b'1')
+                .map(|b| b - b'0');
+            for jj in 0..V_HEIGHT {
+                for col in vram.iter_mut() {
+                    col[jj] = bits.next().ok_or_else(|| bad("truncated image"))?;
+                }
+            }
+        },
+        "P4" => {
+            let raw = data.get(pos + 1..).unwrap_or(&[]); // single whitespace after the header
+            let stride = V_WIDTH.div_ceil(8);
+            if raw.len() < stride * V_HEIGHT { return Err(bad("truncated image")); }
+            for jj in 0..V_HEIGHT {
+                for (ii, col) in vram.iter_mut().enumerate() {
+                    col[jj] = (raw[jj * stride + ii / 8] >> (7 - ii % 8)) & 1;
+                }
+            }
+        },
+        _ => return Err(bad("not a PBM image")),
+    }
+    Ok(vram)
+}
+
+// textual picture of the framebuffer, marking pixels that differ from the expected image
+pub fn diff(got: &Vram, want: &Vram) -> String {
+    let mut s = String::new();
+    for jj in 0..V_HEIGHT {
+        for ii in 0..V_WIDTH {
+            s.push(match (got[ii][jj], want[ii][jj]) {
+                (1, 1) => '#',
+                (0, 0) => '.',
+                (1, _) => '+', // extra pixel
+                _      => '-', // missing pixel
+            });
+        }
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+#[path = "test/headless_test.rs"]
+mod headless_test;
+
+#[cfg(test)]
+#[path = "test/testrom_test.rs"]
+mod testrom_test;
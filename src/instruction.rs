@@ -0,0 +1,185 @@
+use std::fmt;
+
+// decoded opcode
+// mnemonics follow Octo: http://johnearnest.github.io/Octo/docs/chip8ref.pdf
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    Clear,                          // 00e0
+    Return,                         // 00ee
+    Jump(usize),                    // 1nnn
+    Call(usize),                    // 2nnn
+    SkipEq(usize, u8),              // 3xnn
+    SkipNe(usize, u8),              // 4xnn
+    SkipEqReg(usize, usize),        // 5xyn (any n)
+    Load(usize, u8),                // 6xnn
+    Add(usize, u8),                 // 7xnn
+    Move(usize, usize),             // 8xy0
+    Or(usize, usize),               // 8xy1
+    And(usize, usize),              // 8xy2
+    Xor(usize, usize),              // 8xy3
+    AddReg(usize, usize),           // 8xy4
+    Sub(usize, usize),              // 8xy5
+    ShiftRight(usize, usize),       // 8xy6
+    SubFrom(usize, usize),          // 8xy7
+    ShiftLeft(usize, usize),        // 8xye
+    SkipNeReg(usize, usize),        // 9xy0
+    LoadI(usize),                   // annn
+    JumpV0(usize),                  // bnnn
+    Random(usize, u8),              // cxnn
+    Draw(usize, usize, usize),      // dxyn
+    SkipKey(usize),                 // ex9e
+    SkipNoKey(usize),               // exa1
+    GetDelay(usize),                // fx07
+    WaitKey(usize),                 // fx0a
+    SetDelay(usize),                // fx15
+    SetSound(usize),                // fx18
+    AddI(usize),                    // fx1e
+    Font(usize),                    // fx29
+    Bcd(usize),                     // fx33
+    Save(usize),                    // fx55
+    Restore(usize),                 // fx65
+}
+
+impl Instruction {
+    pub fn decode(op: u16) -> Option<Self> {
+        use Instruction::*;
+        // split 2-byte opcode into 4 nibbles
+        let nibs = (
+            (op & 0xf000) >> 12,
+            (op & 0x0f00) >> 8,
+            (op & 0x00f0) >> 4,
+             op & 0x000f,
+        );
+        let nnn = (op & 0x0fff) as usize; // address
+        let nn  = (op & 0x00ff) as u8;    // 8-bit constant
+        let x   = nibs.1 as usize;        // 4-bit constant
+        let y   = nibs.2 as usize;        // 4-bit constant
+        let n   = nibs.3 as usize;        // opcode argument
+
+        Some(match nibs {
+            (0x0, 0x0, 0xe, 0x0) => Clear,
+            (0x0, 0x0, 0xe, 0xe) => Return,
+            (0x1, _,   _,   _  ) => Jump(nnn),
+            (0x2, _,   _,   _  ) => Call(nnn),
+            (0x3, _,   _,   _  ) => SkipEq(x, nn),
+            (0x4, _,   _,   _  ) => SkipNe(x, nn),
+            // n is ignored, so every 5xyn skips like 5xy0
+            (0x5, _,   _,   _  ) => SkipEqReg(x, y),
+            (0x6, _,   _,   _  ) => Load(x, nn),
+            (0x7, _,   _,   _  ) => Add(x, nn),
+            (0x8, _,   _,   0x0) => Move(x, y),
+            (0x8, _,   _,   0x1) => Or(x, y),
+            (0x8, _,   _,   0x2) => And(x, y),
+            (0x8, _,   _,   0x3) => Xor(x, y),
+            (0x8, _,   _,   0x4) => AddReg(x, y),
+            (0x8, _,   _,   0x5) => Sub(x, y),
+            (0x8, _,   _,   0x6) => ShiftRight(x, y),
+            (0x8, _,   _,   0x7) => SubFrom(x, y),
+            (0x8, _,   _,   0xe) => ShiftLeft(x, y),
+            (0x9, _,   _,   0x0) => SkipNeReg(x, y),
+            (0xa, _,   _,   _  ) => LoadI(nnn),
+            (0xb, _,   _,   _  ) => JumpV0(nnn),
+            (0xc, _,   _,   _  ) => Random(x, nn),
+            (0xd, _,   _,   _  ) => Draw(x, y, n),
+            (0xe, _,   0x9, 0xe) => SkipKey(x),
+            (0xe, _,   0xa, 0x1) => SkipNoKey(x),
+            (0xf, _,   0x0, 0x7) => GetDelay(x),
+            (0xf, _,   0x0, 0xa) => WaitKey(x),
+            (0xf, _,   0x1, 0x5) => SetDelay(x),
+            (0xf, _,   0x1, 0x8) => SetSound(x),
+            (0xf, _,   0x1, 0xe) => AddI(x),
+            (0xf, _,   0x2, 0x9) => Font(x),
+            (0xf, _,   0x3, 0x3) => Bcd(x),
+            (0xf, _,   0x5, 0x5) => Save(x),
+            (0xf, _,   0x6, 0x5) => Restore(x),
+            (_,   _,   _,   _  ) => return None,
+        })
+    }
+
+    // opcode pattern, used to group instructions of the same kind
+    pub fn kind(&self) -> &'static str {
+        use Instruction::*;
+        match self {
+            Clear           => "00e0",
+            Return          => "00ee",
+            Jump(_)         => "1nnn",
+            Call(_)         => "2nnn",
+            SkipEq(..)      => "3xnn",
+            SkipNe(..)      => "4xnn",
+            SkipEqReg(..)   => "5xyn",
+            Load(..)        => "6xnn",
+            Add(..)         => "7xnn",
+            Move(..)        => "8xy0",
+            Or(..)          => "8xy1",
+            And(..)         => "8xy2",
+            Xor(..)         => "8xy3",
+            AddReg(..)      => "8xy4",
+            Sub(..)         => "8xy5",
+            ShiftRight(..)  => "8xy6",
+            SubFrom(..)     => "8xy7",
+            ShiftLeft(..)   => "8xye",
+            SkipNeReg(..)   => "9xy0",
+            LoadI(_)        => "annn",
+            JumpV0(_)       => "bnnn",
+            Random(..)      => "cxnn",
+            Draw(..)        => "dxyn",
+            SkipKey(_)      => "ex9e",
+            SkipNoKey(_)    => "exa1",
+            GetDelay(_)     => "fx07",
+            WaitKey(_)      => "fx0a",
+            SetDelay(_)     => "fx15",
+            SetSound(_)     => "fx18",
+            AddI(_)         => "fx1e",
+            Font(_)         => "fx29",
+            Bcd(_)          => "fx33",
+            Save(_)         => "fx55",
+            Restore(_)      => "fx65",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match *self {
+            Clear               => write!(f, "clear"),
+            Return              => write!(f, "return"),
+            Jump(nnn)           => write!(f, "jump {:#05x}", nnn),
+            Call(nnn)           => write!(f, ":call {:#05x}", nnn),
+            SkipEq(x, nn)       => write!(f, "if v{:x} != {:#04x} then", x, nn),
+            SkipNe(x, nn)       => write!(f, "if v{:x} == {:#04x} then", x, nn),
+            SkipEqReg(x, y)     => write!(f, "if v{:x} != v{:x} then", x, y),
+            Load(x, nn)         => write!(f, "v{:x} := {:#04x}", x, nn),
+            Add(x, nn)          => write!(f, "v{:x} += {:#04x}", x, nn),
+            Move(x, y)          => write!(f, "v{:x} := v{:x}", x, y),
+            Or(x, y)            => write!(f, "v{:x} |= v{:x}", x, y),
+            And(x, y)           => write!(f, "v{:x} &= v{:x}", x, y),
+            Xor(x, y)           => write!(f, "v{:x} ^= v{:x}", x, y),
+            AddReg(x, y)        => write!(f, "v{:x} += v{:x}", x, y),
+            Sub(x, y)           => write!(f, "v{:x} -= v{:x}", x, y),
+            ShiftRight(x, y)    => write!(f, "v{:x} >>= v{:x}", x, y),
+            SubFrom(x, y)       => write!(f, "v{:x} =- v{:x}", x, y),
+            ShiftLeft(x, y)     => write!(f, "v{:x} <<= v{:x}", x, y),
+            SkipNeReg(x, y)     => write!(f, "if v{:x} == v{:x} then", x, y),
+            LoadI(nnn)          => write!(f, "i := {:#05x}", nnn),
+            JumpV0(nnn)         => write!(f, "jump0 {:#05x}", nnn),
+            Random(x, nn)       => write!(f, "v{:x} := random {:#04x}", x, nn),
+            Draw(x, y, n)       => write!(f, "sprite v{:x} v{:x} {}", x, y, n),
+            SkipKey(x)          => write!(f, "if v{:x} -key then", x),
+            SkipNoKey(x)        => write!(f, "if v{:x} key then", x),
+            GetDelay(x)         => write!(f, "v{:x} := delay", x),
+            WaitKey(x)          => write!(f, "v{:x} := key", x),
+            SetDelay(x)         => write!(f, "delay := v{:x}", x),
+            SetSound(x)         => write!(f, "buzzer := v{:x}", x),
+            AddI(x)             => write!(f, "i += v{:x}", x),
+            Font(x)             => write!(f, "i := hex v{:x}", x),
+            Bcd(x)              => write!(f, "bcd v{:x}", x),
+            Save(x)             => write!(f, "save v{:x}", x),
+            Restore(x)          => write!(f, "load v{:x}", x),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "test/instruction_test.rs"]
+mod instruction_test;
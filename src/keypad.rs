@@ -1,33 +1,121 @@
-use std::io::{Error, ErrorKind, Read};
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind, Read},
+    sync::mpsc,
+    thread,
+};
 
 use termion::{
-    event::Key,
-    input::TermRead,
+    event::{Event, Key},
+    input::{Events, TermRead},
     AsyncReader,
 };
 
+// extra keys for a ROM, keyboard character to CHIP-8 key
+pub type Keymap = BTreeMap<char, usize>;
+
+// keyboard character for each CHIP-8 key
+pub const LAYOUT: [char; 16] = [
+    '1', '2', '3', '4',
+    'q', 'w', 'e', 'r',
+    'a', 's', 'd', 'f',
+    'z', 'x', 'c', 'v',
+];
+
+// the COSMAC VIP's keypad, as drawn on screen
+pub const GRID: [[usize; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xc],
+    [0x4, 0x5, 0x6, 0xd],
+    [0x7, 0x8, 0x9, 0xe],
+    [0xa, 0x0, 0xb, 0xf],
+];
+
 pub struct Keypad {
-    astdin: AsyncReader, // asynchronous thread to handle input
+    // asynchronous thread to handle input.
+    // the iterator is kept between polls: it can read ahead a byte, which
+    // would be lost if two keys arrived together
+    astdin: Events<Box<dyn Read>>,
 }
 
 impl Keypad {
     pub fn new(stdin: AsyncReader) -> Self {
-        Keypad { astdin: stdin }
+        Keypad { astdin: (Box::new(stdin) as Box<dyn Read>).events() }
     }
 
-    fn getkey(&mut self) -> Option<Result<Key, Error>> {
-        self.astdin.by_ref().keys().nth(0)
+    // read the terminal directly, when stdin was used for the ROM
+    pub fn tty() -> Result<Self, Error> {
+        let tty = termion::get_tty()?;
+        Ok(Keypad { astdin: (Box::new(AsyncTty::new(tty)) as Box<dyn Read>).events() })
+    }
+
+    // next key pressed or mouse event, if any
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        match self.astdin.next() {
+            None         => Ok(None),
+            Some(Err(_)) => Err(Error::new(ErrorKind::InvalidData, "bad key!")),
+            Some(Ok(e))  => Ok(Some(e)),
+        }
+    }
+
+    // next key pressed, if any, skipping mouse events
+    pub fn next_key(&mut self) -> Result<Option<Key>, Error> {
+        while let Some(e) = self.next_event()? {
+            if let Event::Key(k) = e { return Ok(Some(k)); }
+        }
+        Ok(None)
     }
 
     pub fn poll_reader(&mut self) -> Result<[bool; 16], Error> {
-        let k = self.getkey();
-        match k {
-            None        => Ok([false; 16]),
-            Some(event) => match event {
-                Err(_)  => Err(Error::new(ErrorKind::InvalidData, "bad key!")),
-                Ok(key) => poll(Some(key)),
-            },
+        poll(self.next_key()?)
+    }
+}
+
+// termion's AsyncReader, for a reader other than stdin
+struct AsyncTty {
+    recv: mpsc::Receiver<io::Result<u8>>,
+}
+
+impl AsyncTty {
+    fn new<R: Read + Send + 'static>(mut r: R) -> Self {
+        let (send, recv) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0; 64];
+            loop {
+                let res = match r.read(&mut buf) {
+                    Ok(0)  => return,
+                    Ok(n)  => buf[..n].iter().try_for_each(|b| send.send(Ok(*b))),
+                    Err(e) => { let _ = send.send(Err(e)); return; },
+                };
+                if res.is_err() { return; }
+            }
+        });
+        AsyncTty { recv }
+    }
+}
+
+impl Read for AsyncTty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.recv.try_recv() {
+                Ok(Ok(b))  => { buf[n] = b; n += 1; },
+                Ok(Err(e)) => return Err(e),
+                Err(_)     => break,
+            }
         }
+        Ok(n)
+    }
+}
+
+// poll the keypad state, with a ROM's own keys before the usual layout
+pub fn poll_mapped(event: Option<Key>, map: &Keymap) -> Result<[bool; 16], Error> {
+    match event {
+        Some(Key::Char(c)) if map.contains_key(&c) => {
+            let mut keystate = [false; 16];
+            keystate[map[&c]] = true;
+            Ok(keystate)
+        },
+        _ => poll(event),
     }
 }
 
@@ -35,29 +123,11 @@ impl Keypad {
 pub fn poll(event: Option<Key>) -> Result<[bool; 16], Error> {
     let mut keystate = [false; 16];
     match event {
-        None    => Ok(keystate), // no keys pressed
-        Some(k) => match k {
-                Key::Ctrl('c') =>
-                    Err(Error::new(ErrorKind::Interrupted, "exit CHIP8")),
-                Key::Char('1') => { keystate[0x0] = true; Ok(keystate) },
-                Key::Char('2') => { keystate[0x1] = true; Ok(keystate) },
-                Key::Char('3') => { keystate[0x2] = true; Ok(keystate) },
-                Key::Char('4') => { keystate[0x3] = true; Ok(keystate) },
-                Key::Char('q') => { keystate[0x4] = true; Ok(keystate) },
-                Key::Char('w') => { keystate[0x5] = true; Ok(keystate) },
-                Key::Char('e') => { keystate[0x6] = true; Ok(keystate) },
-                Key::Char('r') => { keystate[0x7] = true; Ok(keystate) },
-                Key::Char('a') => { keystate[0x8] = true; Ok(keystate) },
-                Key::Char('s') => { keystate[0x9] = true; Ok(keystate) },
-                Key::Char('d') => { keystate[0xa] = true; Ok(keystate) },
-                Key::Char('f') => { keystate[0xb] = true; Ok(keystate) },
-                Key::Char('z') => { keystate[0xc] = true; Ok(keystate) },
-                Key::Char('x') => { keystate[0xd] = true; Ok(keystate) },
-                Key::Char('c') => { keystate[0xe] = true; Ok(keystate) },
-                Key::Char('v') => { keystate[0xf] = true; Ok(keystate) },
-                _ => Ok(keystate),
-            },
+        Some(Key::Ctrl('c')) => return Err(Error::new(ErrorKind::Interrupted, "exit CHIP8")),
+        Some(Key::Char(c))   => if let Some(k) = LAYOUT.iter().position(|l| *l == c) { keystate[k] = true },
+        _ => (),
     }
+    Ok(keystate)
 }
 
 #[cfg(test)]
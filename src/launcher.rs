@@ -0,0 +1,401 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Error, ErrorKind},
+    mem,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+use termion::{event::Key, raw::IntoRawMode, screen::AlternateScreen};
+use tui::{
+    Frame,
+    Terminal,
+    backend::{Backend, TermionBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::{
+    archive,
+    cpu::Cpu,
+    headless::{self, Vram},
+    keypad::Keypad,
+    quirks::Quirks,
+    rom::ROM,
+    romdb::{self, RomDb},
+    screen::{V_WIDTH, V_HEIGHT},
+    term::{self, suspend, Signals},
+};
+
+// frames run headlessly for the preview (two seconds)
+const PREVIEW_FRAMES: u64 = 120;
+
+// recently played ROMs remembered
+const RECENT: usize = 20;
+
+// favourites and recently played ROMs, kept between runs:
+//
+//   favourite /home/me/roms/pong.ch8
+//   recent /home/me/roms/pack.zip:tank.ch8
+#[derive(Debug, Default, PartialEq)]
+pub struct State {
+    path:           Option<PathBuf>,
+    pub favourites: Vec<String>,
+    pub recent:     Vec<String>,    // most recent first
+}
+
+impl State {
+    // a missing file is an empty state
+    pub fn load(path: Option<PathBuf>) -> Result<Self, Error> {
+        let mut st = State { path, ..State::default() };
+        let text = match &st.path {
+            Some(p) => match fs::read_to_string(p) {
+                Ok(s)  => s,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(st),
+                Err(e) => return Err(e),
+            },
+            None => return Ok(st),
+        };
+        for l in text.lines() {
+            match l.split_once(' ') {
+                Some(("favourite", rom)) => st.favourites.push(rom.to_string()),
+                Some(("recent", rom))    => st.recent.push(rom.to_string()),
+                _ => (),
+            }
+        }
+        Ok(st)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(p) => p,
+            None    => return Ok(()),
+        };
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        let mut s = String::from("# c8rust launcher\n");
+        for rom in &self.favourites { s.push_str(&format!("favourite {}\n", rom)); }
+        for rom in &self.recent { s.push_str(&format!("recent {}\n", rom)); }
+        fs::write(path, s)
+    }
+
+    pub fn toggle_favourite(&mut self, rom: &str) {
+        let n = self.favourites.len();
+        self.favourites.retain(|f| f != rom);
+        if self.favourites.len() == n { self.favourites.push(rom.to_string()); }
+    }
+
+    pub fn played(&mut self, rom: &str) {
+        self.recent.retain(|r| r != rom);
+        self.recent.insert(0, rom.to_string());
+        self.recent.truncate(RECENT);
+    }
+}
+
+// $XDG_CONFIG_HOME/c8rust/launcher, or ~/.config/c8rust/launcher
+pub fn state_path() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config.join("c8rust").join("launcher"))
+}
+
+// what's known about a ROM, worked out when it's first shown
+struct Info {
+    title:      Option<String>,         // from the ROM database
+    details:    Vec<String>,
+    preview:    Preview,
+}
+
+// the first seconds of a ROM, run on a thread of its own once it's selected
+enum Preview {
+    Ready(Box<ROM>, Quirks, Option<usize>),
+    Running(Receiver<Result<Box<Vram>, String>>),
+    Done(Result<Box<Vram>, String>),
+}
+
+impl Preview {
+    // the display once the preview has run, starting it the first time
+    fn poll(&mut self) -> Option<&Result<Box<Vram>, String>> {
+        *self = match mem::replace(self, Preview::Done(Err(String::new()))) {
+            Preview::Ready(r, quirks, ipf) => {
+                let (tx, rx) = mpsc::channel();
+                thread::spawn(move || {
+                    let res = term::catch(move || run_preview(r, quirks, ipf).map_err(|e| e.to_string()));
+                    let _ = tx.send(res.unwrap_or_else(|| Err(String::from("preview failed"))));
+                });
+                Preview::Running(rx)
+            },
+            Preview::Running(rx) => match rx.try_recv() {
+                Ok(res)                         => Preview::Done(res),
+                Err(TryRecvError::Empty)        => Preview::Running(rx),
+                Err(TryRecvError::Disconnected) => Preview::Done(Err(String::from("preview failed"))),
+            },
+            done => done,
+        };
+        match self {
+            Preview::Done(res) => Some(res),
+            _                  => None,
+        }
+    }
+}
+
+// run it with the settings it will have when launched
+fn run_preview(r: Box<ROM>, quirks: Quirks, ipf: Option<usize>) -> Result<Box<Vram>, Error> {
+    let mut c = Cpu::new(Some(*r))?;
+    c.set_quirks(quirks);
+    if let Some(n) = ipf { c.set_ipf(n); }
+    headless::run(&mut c, PREVIEW_FRAMES, None)?;
+    Ok(Box::new(c.vram))
+}
+
+impl Info {
+    fn new(path: &str, db: &RomDb) -> Self {
+        let r = match ROM::new_file(path) {
+            Ok(r)  => r,
+            Err(e) => return Info { title: None, details: Vec::new(), preview: Preview::Done(Err(e.to_string())) },
+        };
+        let sha1 = r.sha1();
+        let entry = db.get(&sha1);
+        let options = r.options().cloned();
+        let mut details = Vec::new();
+        if let Some(e) = entry {
+            details.push(format!("title     {}", e.title));
+            details.push(format!("author    {}", e.author));
+            if let Some(p) = e.platform { details.push(format!("platform  {}", p.name())); }
+        }
+        if options.is_some() { details.push(String::from("format    Octo cartridge")); }
+        details.push(format!("size      {} bytes", r.len()));
+        details.push(format!("sha1      {}", sha1));
+
+        let preview = match romdb::settings(entry, options.as_ref()) {
+            Ok((quirks, ipf)) => {
+                details.push(format!("quirks    {}", quirks.name()));
+                if let Some(n) = ipf { details.push(format!("cycles    {} per frame", n)); }
+                Preview::Ready(Box::new(r), quirks, ipf)
+            },
+            Err(e) => Preview::Done(Err(e.to_string())),
+        };
+        Info {
+            title: entry.map(|e| e.title.clone()).filter(|t| !t.is_empty()),
+            details,
+            preview,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tab {
+    All,
+    Favourites,
+    Recent,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Stay,
+    Launch(String),
+    Quit,
+}
+
+// ROM browser, shown when no ROM is given
+pub struct Launcher {
+    dir:        String,
+    roms:       Vec<String>,
+    db:         RomDb,
+    state:      State,
+    tab:        Tab,
+    sel:        usize,
+    info:       HashMap<String, Info>,
+    message:    Option<String>,         // shown until the next key
+}
+
+impl Launcher {
+    pub fn new(dir: &str, db: RomDb, state: Option<PathBuf>) -> Result<Self, Error> {
+        let dir = fs::canonicalize(dir)?.to_string_lossy().into_owned();
+        let roms = archive::list(&dir)?
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("{}: not a directory or archive", dir)))?;
+        Ok(Launcher {
+            dir,
+            roms,
+            db,
+            state: State::load(state)?,
+            tab: Tab::All,
+            sel: 0,
+            info: HashMap::new(),
+            message: None,
+        })
+    }
+
+    pub fn message(&mut self, m: String) {
+        self.message = Some(m);
+    }
+
+    // pick a ROM, or None to quit
+    pub fn run(&mut self, k: &mut Keypad) -> Result<Option<String>, Error> {
+        let stdout = AlternateScreen::from(io::stdout().into_raw_mode()?);
+        let mut term = Terminal::new(TermionBackend::new(stdout))?;
+        term.clear()?;
+        let signals = Signals::new()?;
+        loop {
+            if signals.poll()? { term.clear()?; }
+            term.draw(|f| self.draw(f, f.size()))?;
+            let key = match k.next_key()? {
+                Some(Key::Ctrl('z')) => { suspend(); term.clear()?; continue; },
+                Some(key) => key,
+                None      => { thread::sleep(Duration::from_millis(10)); continue; },
+            };
+            match self.key(key)? {
+                Action::Stay      => (),
+                Action::Launch(p) => return Ok(Some(p)),
+                Action::Quit      => return Ok(None),
+            }
+        }
+    }
+
+    fn list(&self) -> &[String] {
+        match self.tab {
+            Tab::All        => &self.roms,
+            Tab::Favourites => &self.state.favourites,
+            Tab::Recent     => &self.state.recent,
+        }
+    }
+
+    pub fn key(&mut self, key: Key) -> Result<Action, Error> {
+        self.message = None;
+        let len = self.list().len();
+        let last = len.saturating_sub(1);
+        match key {
+            Key::Up         => self.sel = self.sel.saturating_sub(1),
+            Key::Down       => self.sel = (self.sel + 1).min(last),
+            Key::PageUp     => self.sel = self.sel.saturating_sub(10),
+            Key::PageDown   => self.sel = (self.sel + 10).min(last),
+            Key::Home       => self.sel = 0,
+            Key::End        => self.sel = last,
+            Key::Char('\t') => {
+                self.tab = match self.tab {
+                    Tab::All        => Tab::Favourites,
+                    Tab::Favourites => Tab::Recent,
+                    Tab::Recent     => Tab::All,
+                };
+                self.sel = 0;
+            },
+            Key::Char('f') if len > 0 => {
+                let rom = self.list()[self.sel].clone();
+                self.state.toggle_favourite(&rom);
+                self.sel = self.sel.min(self.list().len().saturating_sub(1));
+                self.state.save()?;
+            },
+            Key::Char('\n') if len > 0 => {
+                let rom = self.list()[self.sel].clone();
+                self.state.played(&rom);
+                self.state.save()?;
+                return Ok(Action::Launch(rom));
+            },
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => return Ok(Action::Quit),
+            _ => (),
+        }
+        Ok(Action::Stay)
+    }
+
+    fn info(&mut self, rom: &str) -> &mut Info {
+        let db = &self.db;
+        self.info.entry(rom.to_string()).or_insert_with(|| Info::new(rom, db))
+    }
+
+    // path under the browsed directory
+    fn name<'a>(&self, rom: &'a str) -> &'a str {
+        rom.strip_prefix(self.dir.as_str()).map_or(rom, |r| r.trim_start_matches('/'))
+    }
+
+    pub fn draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+            .split(area);
+        let cols = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(20), Constraint::Length(V_WIDTH as u16 + 2)].as_ref())
+            .split(rows[0]);
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(V_HEIGHT as u16 / 2 + 2)].as_ref())
+            .split(cols[1]);
+        self.draw_list(f, cols[0]);
+
+        let selected = self.list().get(self.sel).cloned();
+        let (details, preview) = match &selected {
+            Some(rom) => {
+                let info = self.info(rom);
+                let preview = match info.preview.poll() {
+                    Some(Ok(vram)) => preview_lines(vram).into_iter().map(Spans::from).collect(),
+                    Some(Err(e))   => vec![Spans::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))],
+                    None           => vec![Spans::from(Span::styled("running...", Style::default().fg(Color::DarkGray)))],
+                };
+                let details: Vec<Spans> = info.details.iter().cloned().map(Spans::from).collect();
+                (details, preview)
+            },
+            None => (Vec::new(), Vec::new()),
+        };
+        let title = selected.as_deref().map_or(String::new(), |r| self.name(r).to_string());
+        f.render_widget(Paragraph::new(details).block(Block::default().title(title).borders(Borders::ALL)), right[0]);
+        let block = Block::default().title(format!("first {} seconds", PREVIEW_FRAMES / 60)).borders(Borders::ALL);
+        f.render_widget(Paragraph::new(preview).block(block), right[1]);
+
+        let status = match &self.message {
+            Some(m) => Span::styled(m.clone(), Style::default().fg(Color::Red)),
+            None    => Span::raw("Enter play  f favourite  Tab all/favourites/recent  q quit"),
+        };
+        f.render_widget(Paragraph::new(Spans::from(status)), rows[1]);
+    }
+
+    fn draw_list<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let tabs = [(Tab::All, "all"), (Tab::Favourites, "favourites"), (Tab::Recent, "recent")];
+        let title: Vec<Span> = tabs.iter().map(|(t, name)| {
+            let style = if *t == self.tab { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+            Span::styled(format!(" {} ", name), style)
+        }).collect();
+
+        let height = area.height.saturating_sub(2) as usize;
+        let list = self.list().to_vec();
+        let start = self.sel.saturating_sub(height / 2).min(list.len().saturating_sub(height));
+        let mut lines = Vec::new();
+        for (n, rom) in list.iter().enumerate().skip(start).take(height) {
+            let star = if self.state.favourites.contains(rom) { "★" } else { " " };
+            let name = self.name(rom).to_string();
+            let title = self.info(rom).title.clone();
+            let style = if n == self.sel { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+            let mut spans = vec![Span::styled(format!("{} {}", star, name), style)];
+            if let Some(t) = title { spans.push(Span::styled(format!("  {}", t), Style::default().fg(Color::DarkGray))); }
+            lines.push(Spans::from(spans));
+        }
+        if list.is_empty() {
+            let why = match self.tab {
+                Tab::All        => format!("no ROMs in {}", self.dir),
+                Tab::Favourites => String::from("no favourites yet, f marks one"),
+                Tab::Recent     => String::from("nothing played yet"),
+            };
+            lines.push(Spans::from(why));
+        }
+        f.render_widget(Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL)), area);
+    }
+}
+
+// the display as text, two rows of pixels per line
+pub fn preview_lines(vram: &Vram) -> Vec<String> {
+    (0..V_HEIGHT / 2).map(|r| {
+        (0..V_WIDTH).map(|x| match (vram[x][2 * r], vram[x][2 * r + 1]) {
+            (0, 0) => ' ',
+            (_, 0) => '▀',
+            (0, _) => '▄',
+            _      => '█',
+        }).collect()
+    }).collect()
+}
+
+#[cfg(test)]
+#[path = "test/launcher_test.rs"]
+mod launcher_test;
@@ -1,52 +1,641 @@
 extern crate clap;
 
-use clap::{Arg, App};
-use std::io;
+use clap::{Arg, App, AppSettings, ArgMatches, SubCommand};
+use std::{fs, io, panic, path::Path, process, time::Instant};
 use termion::async_stdin; // asynchronous stdin thread for non-blocking keypresses
+use termion::event::{Event, Key, MouseButton, MouseEvent};
 
-mod screen; use screen::Screen;
-mod keypad; use keypad::Keypad;
+mod cast;
+mod movie;  use movie::Movie;
+mod screen; use screen::{Palette, Screen, V_WIDTH, V_HEIGHT};
+mod keypad; use keypad::{Keymap, Keypad};
 mod cpu;    use cpu::Cpu;
 mod rom;    use rom::ROM;
+mod rng;
+mod headless;
+mod instruction;
+mod trace;  use trace::Tracer;
+mod tracediff;
+mod profile;
+mod coverage;
+mod debugger; use debugger::Debugger;
+mod cheat;  use cheat::Cheats;
+mod quirks;
+mod romdb;
+mod patch;
+mod cartridge;
+mod octo;
+mod archive;
+mod launcher; use launcher::Launcher;
+mod menu;   use menu::Menu;
+mod speed;  use speed::Speed;
+mod status; use status::Status;
+mod term;
 
 
-fn main() -> Result<(), io::Error> {
-    let matches = 
+fn main() {
+    term::install_panic_hook();
+    match app() {
+        // quitting with ^C isn't an error
+        Err(e) if e.kind() == io::ErrorKind::Interrupted && term::caught().is_none() => (),
+        Err(e) => {
+            eprintln!("c8rust: {}", e);
+            // a signal that stopped emulation ends the process as it would have
+            if let Some(sig) = term::caught() { term::reraise(sig); }
+            process::exit(1);
+        },
+        Ok(()) => (),
+    }
+}
+
+fn app() -> Result<(), io::Error> {
+    let matches =
         App::new("CHIP8 TUI Emulator")
             .version("1.0")
             .author("Jack Leightcap <jleightcap@protonmail.com>")
             .about("Emulate CHIP8 architecture entirely in the terminal")
+            .setting(AppSettings::SubcommandsNegateReqs)
             .arg(Arg::with_name("INPUT")
-                 .help("Sets input ROM")
-                 .required(true)
+                 .help("Sets input ROM, binary or hex text (`-` for stdin); without one, browse --roms")
                  .index(1))
-            .arg(Arg::with_name("verbose")
-                 .help("Print emulator debug state information")
-                 .short("v")
-                 .long("verbose"))
+            .arg(Arg::with_name("roms")
+                 .help("Directory of ROMs to browse when no INPUT is given")
+                 .long("roms")
+                 .value_name("DIR")
+                 .env("C8RUST_ROMS")
+                 .default_value("."))
+            .arg(Arg::with_name("trace")
+                 .help("Write an execution trace, one record per instruction")
+                 .long("trace")
+                 .value_name("FILE")
+                 .takes_value(true))
+            .arg(Arg::with_name("trace-format")
+                 .help("Execution trace format")
+                 .long("trace-format")
+                 .takes_value(true)
+                 .possible_values(&["text", "json"])
+                 .default_value("text"))
+            .arg(Arg::with_name("trace-range")
+                 .help("Only trace instructions with PC in START:END (hex, inclusive)")
+                 .long("trace-range")
+                 .value_name("START:END")
+                 .takes_value(true)
+                 .requires("trace"))
+            .arg(Arg::with_name("trace-last")
+                 .help("Keep only the last N instructions, written to the trace when the emulator crashes")
+                 .long("trace-last")
+                 .value_name("N")
+                 .takes_value(true)
+                 .requires("trace"))
             .arg(Arg::with_name("nographic")
                  .help("Run emulator without TUI interface")
                  .short("n")
                  .long("nographic"))
+            .arg(Arg::with_name("cast")
+                 .help("Record the TUI session as an asciinema v2 cast file")
+                 .long("cast")
+                 .value_name("FILE")
+                 .takes_value(true)
+                 .conflicts_with("nographic"))
+            .arg(Arg::with_name("record")
+                 .help("Record keypad input and RNG seed to a movie file")
+                 .long("record")
+                 .value_name("FILE")
+                 .takes_value(true))
+            .arg(Arg::with_name("replay")
+                 .help("Replay a recorded movie file instead of reading the keypad")
+                 .long("replay")
+                 .value_name("FILE")
+                 .takes_value(true)
+                 .conflicts_with("record"))
+            .arg(Arg::with_name("profile")
+                 .help("Write an execution profile report on exit (- for stdout)")
+                 .long("profile")
+                 .value_name("FILE")
+                 .takes_value(true))
+            .arg(Arg::with_name("coverage")
+                 .help("Write a code coverage report on exit (- for stdout)")
+                 .long("coverage")
+                 .value_name("FILE")
+                 .takes_value(true))
+            .arg(Arg::with_name("coverage-format")
+                 .help("Code coverage report format")
+                 .long("coverage-format")
+                 .takes_value(true)
+                 .possible_values(&["annotated", "lcov"])
+                 .default_value("annotated"))
+            .arg(Arg::with_name("patch")
+                 .help("Apply an IPS or BPS patch to the ROM when loading it")
+                 .long("patch")
+                 .value_name("FILE")
+                 .takes_value(true))
+            .args(&rom_args())
+            .arg(Arg::with_name("cheats")
+                 .help("Cheat file: cheats for this ROM are applied on load, and saved when changed in the debugger")
+                 .long("cheats")
+                 .value_name("FILE")
+                 .takes_value(true)
+                 .conflicts_with("record"))
+            .arg(Arg::with_name("seed")
+                 .help("Seed for the random number generator (default: random)")
+                 .long("seed")
+                 .value_name("N")
+                 .takes_value(true))
+            .arg(Arg::with_name("rng")
+                 .help("Random number generator used by Cxnn")
+                 .long("rng")
+                 .takes_value(true)
+                 .possible_values(&["std"])
+                 .default_value("std"))
+            .subcommand(SubCommand::with_name("test")
+                .about("Run a ROM headlessly and check the final screen")
+                .arg(Arg::with_name("ROM")
+                     .help("ROM to run, binary or hex text (`-` for stdin)")
+                     .required(true)
+                     .index(1))
+                .arg(Arg::with_name("patch")
+                     .help("Apply an IPS or BPS patch to the ROM when loading it")
+                     .long("patch")
+                     .value_name("FILE")
+                     .takes_value(true))
+                .args(&rom_args())
+                .arg(Arg::with_name("frames")
+                     .help("Number of 60 Hz frames to run")
+                     .long("frames")
+                     .value_name("N")
+                     .takes_value(true)
+                     .default_value("60"))
+                .arg(Arg::with_name("expect")
+                     .help("Golden .pbm image or framebuffer hash to compare against")
+                     .long("expect")
+                     .value_name("FILE|HASH")
+                     .takes_value(true))
+                .arg(Arg::with_name("input")
+                     .help("Movie file providing keypad input and RNG seed")
+                     .long("input")
+                     .value_name("FILE")
+                     .takes_value(true))
+                .arg(Arg::with_name("seed")
+                     .help("Seed for the random number generator (ignored with --input)")
+                     .long("seed")
+                     .value_name("N")
+                     .takes_value(true))
+                .arg(Arg::with_name("rng")
+                     .help("Random number generator used by Cxnn (ignored with --input)")
+                     .long("rng")
+                     .takes_value(true)
+                     .possible_values(&["std"])
+                     .default_value("std"))
+                .arg(Arg::with_name("dump")
+                     .help("Write the final screen as a .pbm image")
+                     .long("dump")
+                     .value_name("FILE")
+                     .takes_value(true))
+                .arg(Arg::with_name("profile")
+                     .help("Write an execution profile report (- for stdout)")
+                     .long("profile")
+                     .value_name("FILE")
+                     .takes_value(true))
+                .arg(Arg::with_name("coverage")
+                     .help("Write a code coverage report (- for stdout)")
+                     .long("coverage")
+                     .value_name("FILE")
+                     .takes_value(true))
+                .arg(Arg::with_name("coverage-format")
+                     .help("Code coverage report format")
+                     .long("coverage-format")
+                     .takes_value(true)
+                     .possible_values(&["annotated", "lcov"])
+                     .default_value("annotated")))
+            .subcommand(SubCommand::with_name("mkpatch")
+                .about("Write an IPS patch turning one ROM into another")
+                .arg(Arg::with_name("ORIGINAL")
+                     .help("Unmodified ROM")
+                     .required(true)
+                     .index(1))
+                .arg(Arg::with_name("MODIFIED")
+                     .help("Modified ROM")
+                     .required(true)
+                     .index(2))
+                .arg(Arg::with_name("output")
+                     .help("Patch file to write (default: stdout)")
+                     .short("o")
+                     .long("output")
+                     .value_name("FILE")
+                     .takes_value(true)))
+            .subcommand(SubCommand::with_name("tracediff")
+                .about("Report the first divergence between two execution traces")
+                .arg(Arg::with_name("A")
+                     .help("First trace (text or JSON Lines)")
+                     .required(true)
+                     .index(1))
+                .arg(Arg::with_name("B")
+                     .help("Second trace (text or JSON Lines)")
+                     .required(true)
+                     .index(2)))
             .get_matches();
-    let fname = matches.value_of("INPUT").unwrap();
-    let verbo = matches.is_present("verbose");
+
+    match matches.subcommand() {
+        ("test", Some(sub))      => return test(sub),
+        ("tracediff", Some(sub)) => return tracediff(sub),
+        ("mkpatch", Some(sub))   => return mkpatch(sub),
+        _ => (),
+    }
+
+    match matches.value_of("INPUT") {
+        Some(input) => {
+            let fname = resolve(input, termion::is_tty(&io::stdin()))?;
+            let mut k = keypad(&fname)?;
+            play(&fname, &matches, &mut k)
+        },
+        None => launch(&matches),
+    }
+}
+
+// no ROM given: run the ones picked in the launcher until it's quit
+fn launch(m: &ArgMatches) -> Result<(), io::Error> {
+    let db = romdb::RomDb::load(m.value_of("romdb"))?;
+    let mut l = Launcher::new(m.value_of("roms").unwrap(), db, launcher::state_path())?;
+    let mut k = Keypad::new(async_stdin());
+    while let Some(path) = l.run(&mut k)? {
+        match play(&path, m, &mut k) {
+            Err(e) if term::caught().is_some() => return Err(e),
+            Err(e) if e.kind() != io::ErrorKind::Interrupted => l.message(format!("{}: {}", path, e)),
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+// run a ROM in the terminal, or replay a movie
+fn play(fname: &str, matches: &ArgMatches, k: &mut Keypad) -> Result<(), io::Error> {
     let blank = matches.is_present("nographic");
+    let cast  = matches.value_of("cast");
+    let rec   = matches.value_of("record");
+    let play  = matches.value_of("replay");
+    let seed  = match matches.value_of("seed") {
+        None    => rand::random::<u64>(),
+        Some(s) => parse_num(s, "seed")?,
+    };
+    let rng   = matches.value_of("rng").unwrap();
 
     // components
-    let mut screen = Screen::new(!blank)?;
-    let mut k = Keypad::new(async_stdin());
-    let r = ROM::new_file(&fname);
+    let r = load_rom(fname, matches.value_of("patch"))?;
+    let cheats = Cheats::load(matches.value_of("cheats"), &r.sha1())?;
+    let db = romdb::RomDb::load(matches.value_of("romdb"))?;
+    let entry = db.get(&r.sha1());
+    let options = r.options().cloned();
+    let palette = options.as_ref().map(palette).unwrap_or_default();
+    let mut c: Cpu = Cpu::new(Some(r))?;
+    let keymap = configure(&mut c, entry, options.as_ref(), matches)?;
+    if let Some(path) = matches.value_of("trace") {
+        c.set_tracer(tracer(path, matches)?);
+    }
+    if matches.is_present("profile") { c.enable_profile(); }
+    if matches.is_present("coverage") { c.enable_coverage(); }
+
+    if let Some(path) = play {
+        let movie = Movie::load(path)?;
+        let res = replay(&mut c, k, &movie, blank, cast, palette);
+        write_reports(&c, fname, matches)?;
+        return crashed(&mut c, res);
+    }
+    c.set_rng(rng::from_name(rng, seed)?);
+    c.reseed(seed);
+
+    let mut screen = Screen::new(!blank, cast)?.map(|s| s.palette(palette));
+    let mut movie = Movie::new(c.seed(), c.rng_name()).machine(&c);
+
+    cheats.apply_all(&mut c);
+    let dbg = Debugger::new().cheats(cheats).locked(rec.is_some());
+    let title = entry.map(|e| e.title.as_str()).filter(|t| !t.is_empty()).unwrap_or_else(|| rom_name(fname));
+    let status = Status::new(title, &c, Instant::now());
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        run(&mut c, &mut screen, k, &keymap, dbg, status, rec.map(|_| &mut movie))
+    }));
+    let res = match res {
+        Ok(res) => res,
+        Err(p)  => {
+            // the panic hook has given the terminal back and said why
+            drop(screen);
+            eprint!("{}", c.state());
+            panic::resume_unwind(p);
+        },
+    };
+
+    // save whatever was recorded, even when exiting on error or ^C
+    if let Some(path) = rec { movie.save(path)?; }
+    drop(screen); // restore the terminal before printing the reports
+    write_reports(&c, fname, matches)?;
+    crashed(&mut c, res)
+}
+
+// ROM database and machine options, shared by running and testing
+fn rom_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("romdb")
+            .help("ROM database (JSON, by SHA-1) to use alongside the built-in one, which only knows the test ROMs")
+            .long("romdb")
+            .value_name("FILE")
+            .takes_value(true),
+        Arg::with_name("platform")
+            .help("Platform quirks to follow, instead of the ROM database's")
+            .long("platform")
+            .takes_value(true)
+            .possible_values(&["chip8", "schip", "xochip"]),
+        Arg::with_name("quirks")
+            .help("Quirks to turn on, or off with a leading -, e.g. \"jumping,-clipping\"")
+            .long("quirks")
+            .value_name("LIST")
+            .takes_value(true),
+        Arg::with_name("ipf")
+            .help("Machine cycles per 60 Hz frame (default: the ROM database's, or 10)")
+            .long("ipf")
+            .value_name("N")
+            .takes_value(true),
+    ]
+}
+
+// set up the Cpu for a ROM from its database entry, its cartridge options,
+// then the command line; returns the ROM's extra keys
+fn configure(
+    c: &mut Cpu,
+    entry: Option<&romdb::Entry>,
+    options: Option<&cartridge::Options>,
+    m: &ArgMatches,
+) -> Result<Keymap, io::Error> {
+    let (mut quirks, ipf) = romdb::settings(entry, options)?;
+    if let Some(p) = m.value_of("platform") {
+        quirks = quirks::Quirks::platform(quirks::Platform::from_name(p)?);
+    }
+    if let Some(q) = m.value_of("quirks") { quirks.apply(q)?; }
+    c.set_quirks(quirks);
+    match m.value_of("ipf") {
+        Some(n) => c.set_ipf(parse_num(n, "cycles per frame")?),
+        None    => if let Some(n) = ipf { c.set_ipf(n) },
+    }
+    Ok(entry.map(|e| e.keymap.clone()).unwrap_or_default())
+}
+
+// an Octo cartridge's colours, the defaults where it has none
+fn palette(o: &cartridge::Options) -> Palette {
+    let rgb = |c: Option<cartridge::Rgb>, d| c.map_or(d, |(r, g, b)| tui::style::Color::Rgb(r, g, b));
+    let p = Palette::default();
+    let bg = rgb(o.bg, p.bg);
+    Palette { fg: rgb(o.fg, p.fg), bg, buzz: rgb(o.buzz, bg) }
+}
+
+// keys come from the terminal itself when the ROM was read from stdin
+fn keypad(fname: &str) -> Result<Keypad, io::Error> {
+    if fname == "-" { Keypad::tty() } else { Ok(Keypad::new(async_stdin())) }
+}
+
+// a directory or archive of ROMs lists them, to pick one by number
+fn resolve(path: &str, interactive: bool) -> Result<String, io::Error> {
+    let roms = match archive::list(path)? {
+        None       => return Ok(path.to_string()),
+        Some(roms) => roms,
+    };
+    if roms.is_empty() {
+        let exts = archive::EXTENSIONS.iter().map(|e| format!(".{}", e)).collect::<Vec<_>>().join(", ");
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{}: no ROMs ({}) found", path, exts)));
+    }
+    if roms.len() == 1 { return Ok(roms[0].clone()); }
+    for (n, r) in roms.iter().enumerate() { eprintln!("{:>4}  {}", n + 1, r); }
+    if !interactive {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} holds several ROMs, name one of the above", path)));
+    }
+    eprint!("ROM number: ");
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    line.trim().parse::<usize>().ok()
+        .and_then(|n| roms.get(n.wrapping_sub(1)).cloned())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("no ROM number \"{}\"", line.trim())))
+}
+
+// a ROM's file name, or its name in an archive
+fn rom_name(path: &str) -> &str {
+    let name = archive::split(path).map_or(path, |(_, member)| member);
+    match name {
+        "-" => "stdin",
+        _   => Path::new(name).file_name().and_then(|n| n.to_str()).unwrap_or(name),
+    }
+}
 
-    let mut c: Cpu = Cpu::new(Some(r), verbo)?;
+// load a ROM, applying --patch
+fn load_rom(path: &str, patch: Option<&str>) -> Result<ROM, io::Error> {
+    let r = ROM::new_file(path)?;
+    match patch {
+        None    => Ok(r),
+        Some(p) => r.patch(&fs::read(p)?).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", p, e))),
+    }
+}
+
+// write the --profile and --coverage reports
+fn write_reports(c: &Cpu, rom: &str, m: &ArgMatches) -> Result<(), io::Error> {
+    write_report(m.value_of("profile"), c.profile_report())?;
+    let format = coverage::Format::from_name(m.value_of("coverage-format").unwrap())?;
+    write_report(m.value_of("coverage"), c.coverage_report(format, rom))
+}
+
+fn write_report(path: Option<&str>, report: Option<String>) -> Result<(), io::Error> {
+    match (path, report) {
+        (Some("-"), Some(r))  => { print!("{}", r); Ok(()) },
+        (Some(p), Some(r))    => fs::write(p, r),
+        _ => Ok(()),
+    }
+}
+
+// build the tracer from the --trace* options
+fn tracer(path: &str, m: &ArgMatches) -> Result<Tracer, io::Error> {
+    let format = trace::Format::from_name(m.value_of("trace-format").unwrap())?;
+    let mut t = Tracer::create(path, format)?;
+    if let Some(r) = m.value_of("trace-range") {
+        let bad = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid trace range \"{}\"", r));
+        let (start, end) = r.split_once(':').ok_or_else(bad)?;
+        let start = usize::from_str_radix(start.trim_start_matches("0x"), 16).map_err(|_| bad())?;
+        let end = usize::from_str_radix(end.trim_start_matches("0x"), 16).map_err(|_| bad())?;
+        t = t.range(start, end);
+    }
+    if let Some(n) = m.value_of("trace-last") {
+        t = t.last(parse_num(n, "instruction count")?);
+    }
+    Ok(t)
+}
 
+// on a crash or a signal (anything but the user quitting), say where the
+// machine was and flush the last traced instructions
+fn crashed(c: &mut Cpu, res: Result<(), io::Error>) -> Result<(), io::Error> {
+    if let Err(e) = &res {
+        if e.kind() != io::ErrorKind::Interrupted || term::caught().is_some() {
+            eprint!("{}", c.state());
+            c.dump_trace()?;
+        }
+    }
+    res
+}
 
+fn run(
+    c: &mut Cpu,
+    screen: &mut Option<Screen>,
+    k: &mut Keypad,
+    keymap: &Keymap,
+    mut dbg: Debugger,
+    mut status: Status,
+    mut movie: Option<&mut Movie>,
+) -> Result<(), io::Error> {
+    let mut menu = Menu::new(movie.is_some());
+    let mut speed = Speed::new(movie.is_some());
+    let mut clicked = None; // key held down with the mouse on the on-screen keypad
+    let signals = term::Signals::new()?;
+    // one 60 Hz tick at a time, running as many frames as the speed says
     loop {
-        let key = k.poll_reader()?;
-        c.mcycle(&key)?;
-        match &mut screen {
-            Some(s) => s.render(&c.vram),
-            None    => (),
+        if signals.poll()? {
+            if let Some(s) = screen { s.redraw() }
+        }
+        // keys the menu, debugger and speed controls don't use go to the keypad,
+        // held for every frame of the tick
+        let mut key = [false; 16];
+        while let Some(event) = k.next_event()? {
+            let pressed = match event {
+                // raw mode leaves ^Z to us
+                Event::Key(Key::Ctrl('z')) => {
+                    term::suspend();
+                    if let Some(s) = screen { s.redraw() }
+                    continue;
+                },
+                Event::Key(e) if menu.key(e, c, &mut dbg)? || dbg.key(e, c)? || speed.key(e, c) => continue,
+                Event::Key(e) => keypad::poll_mapped(Some(e), keymap)?,
+                Event::Mouse(MouseEvent::Press(MouseButton::Left, x, y)) | Event::Mouse(MouseEvent::Hold(x, y)) => {
+                    clicked = screen.as_ref().and_then(|s| s.key_at(x, y));
+                    continue;
+                },
+                Event::Mouse(MouseEvent::Release(..)) => { clicked = None; continue; },
+                _ => continue,
+            };
+            key.iter_mut().zip(pressed.iter()).for_each(|(k, p)| *k |= p);
         }
+        if let Some(n) = clicked { key[n] = true; }
+        for _ in 0..speed.frames(dbg.paused || menu.open) {
+            let buzz = c.buzzing();
+            let done = dbg.frame(c, &key)?;
+            if c.buzzing() && !buzz { print!("\x07"); } // BEEP
+            match (done, &mut movie) {
+                // stopped at a breakpoint
+                (None, _) => break,
+                // frames are recorded once complete, with the keys they ran with
+                (Some(k), Some(m)) => m.push(&k),
+                (Some(_), None) => (),
+            }
+        }
+        status.update(c, Instant::now());
+        if let Some(s) = screen {
+            s.set_status(status.line(c, &speed.describe(c), dbg.paused || menu.open));
+            s.render(c, Some(&mut dbg), Some(&menu))?;
+        }
+        speed.wait();
     }
 }
+
+// feed recorded input back into the CPU; headless replays print the final screen
+fn replay(c: &mut Cpu, k: &mut Keypad, movie: &Movie, blank: bool, cast: Option<&str>, palette: Palette) -> Result<(), io::Error> {
+    movie.setup(c)?;
+    let mut screen = Screen::new(!blank, cast)?.map(|s| s.palette(palette));
+    let mut speed = Speed::new(true);
+    for key in movie.frames() {
+        // still poll the keyboard so a graphical replay can be interrupted
+        if !blank { k.poll_reader()?; }
+        c.frame(&key)?;
+        if let Some(s) = &mut screen {
+            s.render(c, None, None)?;
+            speed.wait();
+        }
+    }
+    if blank {
+        for jj in 0..V_HEIGHT {
+            let row: String = (0..V_WIDTH).map(|ii| if c.vram[ii][jj] == 1 { '#' } else { '.' }).collect();
+            println!("{}", row);
+        }
+    }
+    Ok(())
+}
+
+// `test` subcommand: run headlessly, report the framebuffer hash and
+// fail if it doesn't match the expected image
+fn test(m: &ArgMatches) -> Result<(), io::Error> {
+    let fname = &resolve(m.value_of("ROM").unwrap(), false)?;
+    let r = load_rom(fname, m.value_of("patch"))?;
+    let db = romdb::RomDb::load(m.value_of("romdb"))?;
+    let entry = db.get(&r.sha1());
+    let options = r.options().cloned();
+    let mut c = Cpu::new(Some(r))?;
+    configure(&mut c, entry, options.as_ref(), m)?;
+    let frames = parse_num(m.value_of("frames").unwrap(), "frame count")?;
+
+    let input = match m.value_of("input") {
+        None    => None,
+        Some(p) => Some(Movie::load(p)?),
+    };
+    match &input {
+        Some(movie) => movie.setup(&mut c)?,
+        None => {
+            let seed = match m.value_of("seed") {
+                None    => rng::DEFAULT_SEED,
+                Some(s) => parse_num(s, "seed")?,
+            };
+            c.set_rng(rng::from_name(m.value_of("rng").unwrap(), seed)?);
+            c.reseed(seed);
+        },
+    }
+
+    if m.is_present("profile") { c.enable_profile(); }
+    if m.is_present("coverage") { c.enable_coverage(); }
+    headless::run(&mut c, frames, input.as_ref())?;
+    write_reports(&c, fname, m)?;
+    println!("hash {:016x}", headless::hash(&c.vram));
+    if let Some(p) = m.value_of("dump") {
+        fs::write(p, headless::pbm_write(&c.vram))?;
+    }
+    if let Some(e) = m.value_of("expect") {
+        let expect = headless::Expect::parse(e)?;
+        if !expect.matches(&c.vram) {
+            if let headless::Expect::Image(want) = &expect {
+                eprint!("{}", headless::diff(&c.vram, want));
+            }
+            return Err(io::Error::other("screen does not match expected output"));
+        }
+        println!("ok");
+    }
+    Ok(())
+}
+
+// `tracediff` subcommand: fail on the first difference between two traces
+fn tracediff(m: &ArgMatches) -> Result<(), io::Error> {
+    let a = tracediff::load(m.value_of("A").unwrap())?;
+    let b = tracediff::load(m.value_of("B").unwrap())?;
+    match tracediff::first_divergence(&a, &b) {
+        None => {
+            println!("traces match ({} instructions)", a.len().min(b.len()));
+            Ok(())
+        },
+        Some(d) => {
+            print!("{}", tracediff::report(&d));
+            Err(io::Error::other("traces diverge"))
+        },
+    }
+}
+
+// `mkpatch` subcommand: IPS patch from one ROM to another
+fn mkpatch(m: &ArgMatches) -> Result<(), io::Error> {
+    let original = fs::read(m.value_of("ORIGINAL").unwrap())?;
+    let modified = fs::read(m.value_of("MODIFIED").unwrap())?;
+    let ips = patch::make_ips(&original, &modified);
+    match m.value_of("output") {
+        Some(p) => fs::write(p, ips),
+        None    => io::Write::write_all(&mut io::stdout(), &ips),
+    }
+}
+
+fn parse_num<T: std::str::FromStr>(s: &str, what: &str) -> Result<T, io::Error> {
+    s.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid {} \"{}\"", what, s)))
+}
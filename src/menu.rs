@@ -0,0 +1,180 @@
+use std::io::{Error, ErrorKind};
+
+use termion::event::Key;
+use tui::{
+    Frame,
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    cpu::{Cpu, Snapshot},
+    debugger::Debugger,
+    quirks::NAMES as QUIRKS,
+};
+
+// saved states kept for the session
+pub const SLOTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item {
+    Resume,
+    Reset,
+    Save,
+    Load,
+    Speed,
+    Quirk(usize),
+    Debugger,
+    Quit,
+}
+
+const ITEMS: [Item; 12] = [
+    Item::Resume, Item::Reset, Item::Save, Item::Load, Item::Speed,
+    Item::Quirk(0), Item::Quirk(1), Item::Quirk(2), Item::Quirk(3), Item::Quirk(4),
+    Item::Debugger, Item::Quit,
+];
+
+// pause menu drawn over the display, opened with Esc; F5 resets directly.
+// emulation is paused while it's open
+pub struct Menu {
+    pub open:   bool,
+    sel:        usize,
+    slot:       usize,
+    slots:      Vec<Option<Snapshot>>,
+    locked:     bool,           // recording a movie, which only holds key presses
+    message:    Option<String>,
+}
+
+impl Menu {
+    pub fn new(locked: bool) -> Self {
+        Menu {
+            open: false,
+            sel: 0,
+            slot: 0,
+            slots: (0..SLOTS).map(|_| None).collect(),
+            locked,
+            message: None,
+        }
+    }
+
+    // handle a key, returning false if it should go on to the debugger and keypad
+    pub fn key(&mut self, key: Key, c: &mut Cpu, dbg: &mut Debugger) -> Result<bool, Error> {
+        self.message = None;
+        if !self.open {
+            match key {
+                Key::Esc if !dbg.editing() => self.open = true,
+                // opening the menu to say why when it can't
+                Key::F(5) => { self.reset(c, dbg); self.open = self.message.is_some(); },
+                _ => return Ok(false),
+            }
+            return Ok(true);
+        }
+        let item = ITEMS[self.sel];
+        match key {
+            Key::Esc        => self.open = false,
+            Key::Up         => self.sel = (self.sel + ITEMS.len() - 1) % ITEMS.len(),
+            Key::Down       => self.sel = (self.sel + 1) % ITEMS.len(),
+            Key::Left | Key::Right => {
+                let right = key == Key::Right;
+                match item {
+                    Item::Save | Item::Load => {
+                        self.slot = if right { (self.slot + 1) % SLOTS } else { (self.slot + SLOTS - 1) % SLOTS };
+                    },
+                    Item::Speed if self.unlocked() => {
+                        let ipf = c.ipf();
+                        c.set_ipf(if right { ipf + 1 } else { ipf.saturating_sub(1) });
+                    },
+                    Item::Quirk(q) => self.toggle(c, q),
+                    _ => (),
+                }
+            },
+            Key::Char('\n') | Key::Char(' ') => match item {
+                Item::Resume    => self.open = false,
+                Item::Reset     => { self.reset(c, dbg); self.open = false; },
+                Item::Save      => {
+                    self.slots[self.slot] = Some(c.snapshot());
+                    self.message = Some(format!("saved to slot {}", self.slot + 1));
+                },
+                Item::Load      => if self.unlocked() {
+                    match &self.slots[self.slot] {
+                        None     => self.message = Some(format!("slot {} is empty", self.slot + 1)),
+                        Some(st) => { c.restore(st); self.open = false; },
+                    }
+                },
+                Item::Speed     => (),
+                Item::Quirk(q)  => self.toggle(c, q),
+                Item::Debugger  => { dbg.open = true; self.open = false; },
+                Item::Quit      => return Err(Error::new(ErrorKind::Interrupted, "exit CHIP8")),
+            },
+            Key::Ctrl('c') => return Err(Error::new(ErrorKind::Interrupted, "exit CHIP8")),
+            _ => (),
+        }
+        Ok(true)
+    }
+
+    // changes a movie couldn't replay are refused while recording
+    fn unlocked(&mut self) -> bool {
+        if self.locked { self.message = Some(String::from("not while recording a movie")); }
+        !self.locked
+    }
+
+    fn reset(&mut self, c: &mut Cpu, dbg: &mut Debugger) {
+        if self.unlocked() {
+            c.reset();
+            dbg.reset(c);
+        }
+    }
+
+    fn toggle(&mut self, c: &mut Cpu, q: usize) {
+        if !self.unlocked() { return; }
+        let mut quirks = c.quirks();
+        let on = quirks.get(QUIRKS[q]);
+        // safe unwrap: QUIRKS are all known names
+        quirks.set(QUIRKS[q], !on).unwrap();
+        c.set_quirks(quirks);
+    }
+
+    fn line(&self, item: Item, c: &Cpu) -> String {
+        let onoff = |b| if b { "on" } else { "off" };
+        let slot = |n: usize| match &self.slots[n] {
+            Some(_) => format!("slot {}", n + 1),
+            None    => format!("slot {} (empty)", n + 1),
+        };
+        match item {
+            Item::Resume    => String::from("resume"),
+            Item::Reset     => String::from("reset                 F5"),
+            Item::Save      => format!("save state   ◂ {} ▸", slot(self.slot)),
+            Item::Load      => format!("load state   ◂ {} ▸", slot(self.slot)),
+            Item::Speed     => format!("speed        ◂ {} cycles/frame ▸", c.ipf()),
+            Item::Quirk(q)  => format!("  {:<10} {}", QUIRKS[q], onoff(c.quirks().get(QUIRKS[q]))),
+            Item::Debugger  => String::from("debugger              F1"),
+            Item::Quit      => String::from("quit                  ^C"),
+        }
+    }
+
+    // centred over the area
+    pub fn draw<B: Backend>(&self, f: &mut Frame<B>, area: Rect, c: &Cpu) {
+        let mut lines: Vec<Spans> = Vec::new();
+        for (n, item) in ITEMS.iter().enumerate() {
+            if *item == Item::Quirk(0) {
+                lines.push(Spans::from(format!("quirks       {}", c.quirks().name())));
+            }
+            let style = if n == self.sel { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+            lines.push(Spans::from(Span::styled(self.line(*item, c), style)));
+        }
+        if let Some(m) = &self.message {
+            lines.push(Spans::from(Span::styled(m.clone(), Style::default().fg(Color::Yellow))));
+        }
+        let (w, h) = (38.min(area.width), (lines.len() as u16 + 2).min(area.height));
+        let r = Rect::new(area.x + (area.width - w) / 2, area.y + (area.height - h) / 2, w, h);
+        f.render_widget(Clear, r);
+        f.render_widget(Paragraph::new(lines).block(Block::default().title("paused").borders(Borders::ALL)), r);
+    }
+}
+
+#[cfg(test)]
+#[path = "test/menu_test.rs"]
+mod menu_test;
@@ -0,0 +1,143 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use crate::{
+    cpu::Cpu,
+    quirks::Quirks,
+    rng,
+};
+
+const MAGIC: &str = "c8rust-movie 2";
+
+// version 1 movies held a keypad state per machine cycle, which can't be replayed a frame at a time
+const MAGIC_V1: &str = "c8rust-movie 1";
+
+// recorded input: the RNG and its seed, and the keypad state of every 60 Hz frame.
+// the cycles per frame and quirks it was recorded with are kept too, so it
+// replays the same whatever the command line or ROM database say.
+//
+// stored as text, run-length encoded since the keypad is idle most of the time:
+//   c8rust-movie 2
+//   seed <seed>
+//   rng <name>             (optional, defaults to std)
+//   ipf <cycles per frame> (optional)
+//   quirks <list>          (optional, every quirk as --quirks reads them)
+//   <count> <keypad bitmask, bit n = key n>
+//   ...
+#[derive(Clone, Debug, PartialEq)]
+pub struct Movie {
+    pub seed:   u64,
+    pub rng:    String,
+    pub ipf:    Option<usize>,
+    pub quirks: Option<Quirks>,
+    runs:       Vec<(u64, u16)>,
+}
+
+impl Movie {
+    pub fn new(seed: u64, rng: &str) -> Self {
+        Movie { seed, rng: rng.to_string(), ipf: None, quirks: None, runs: Vec::new() }
+    }
+
+    // the machine being recorded
+    pub fn machine(mut self, c: &Cpu) -> Self {
+        self.ipf = Some(c.ipf());
+        self.quirks = Some(c.quirks());
+        self
+    }
+
+    // set the machine up as it was recorded, overriding any other settings
+    pub fn setup(&self, c: &mut Cpu) -> Result<(), Error> {
+        c.set_rng(rng::from_name(&self.rng, self.seed)?);
+        c.reseed(self.seed);
+        if let Some(n) = self.ipf { c.set_ipf(n); }
+        if let Some(q) = self.quirks { c.set_quirks(q); }
+        Ok(())
+    }
+
+    // append the keypad state of one frame
+    pub fn push(&mut self, keys: &[bool; 16]) {
+        let mask = to_mask(keys);
+        match self.runs.last_mut() {
+            Some((n, m)) if *m == mask => *n += 1,
+            _ => self.runs.push((1, mask)),
+        }
+    }
+
+    // keypad state of every recorded frame, in order
+    pub fn frames(&self) -> impl Iterator<Item = [bool; 16]> + '_ {
+        self.runs.iter().flat_map(|&(n, m)| (0..n).map(move |_| from_mask(m)))
+    }
+
+    pub fn load(path: &str) -> Result<Self, Error> {
+        Movie::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let bad = |what: &str| Error::new(ErrorKind::InvalidData, format!("movie: {}", what));
+        let mut lines = s.lines();
+        match lines.next() {
+            Some(MAGIC)    => (),
+            Some(MAGIC_V1) => return Err(bad("version 1 movies hold keys per machine cycle and can't be replayed; record it again")),
+            _              => return Err(bad("not a movie file")),
+        }
+        let seed = lines.next()
+            .and_then(|l| l.strip_prefix("seed "))
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| bad("missing seed"))?;
+        let mut m = Movie::new(seed, "std");
+        for l in lines.filter(|l| !l.trim().is_empty()) {
+            if let Some(name) = l.strip_prefix("rng ") {
+                m.rng = name.trim().to_string();
+                continue;
+            }
+            if let Some(n) = l.strip_prefix("ipf ") {
+                m.ipf = Some(n.trim().parse().map_err(|_| bad(&format!("bad ipf \"{}\"", l)))?);
+                continue;
+            }
+            if let Some(list) = l.strip_prefix("quirks ") {
+                let mut q = Quirks::default();
+                q.apply(list).map_err(|e| bad(&e.to_string()))?;
+                m.quirks = Some(q);
+                continue;
+            }
+            let mut f = l.split_whitespace();
+            let n = f.next().and_then(|v| v.parse().ok());
+            let k = f.next().and_then(|v| u16::from_str_radix(v, 16).ok());
+            match (n, k) {
+                (Some(n), Some(k)) => m.runs.push((n, k)),
+                _ => return Err(bad(&format!("bad frame \"{}\"", l))),
+            }
+        }
+        Ok(m)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut s = format!("{}\nseed {}\nrng {}\n", MAGIC, self.seed, self.rng);
+        if let Some(n) = self.ipf { s.push_str(&format!("ipf {}\n", n)); }
+        if let Some(q) = &self.quirks { s.push_str(&format!("quirks {}\n", q.list())); }
+        for (n, m) in &self.runs {
+            s.push_str(&format!("{} {:04x}\n", n, m));
+        }
+        s
+    }
+}
+
+fn to_mask(keys: &[bool; 16]) -> u16 {
+    keys.iter().enumerate().fold(0, |m, (ii, k)| if *k { m | 1 << ii } else { m })
+}
+
+fn from_mask(m: u16) -> [bool; 16] {
+    let mut keys = [false; 16];
+    for (ii, k) in keys.iter_mut().enumerate() { *k = m & (1 << ii) != 0; }
+    keys
+}
+
+#[cfg(test)]
+#[path = "test/movie_test.rs"]
+mod movie_test;
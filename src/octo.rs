@@ -0,0 +1,446 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind},
+};
+
+// compiler for the part of Octo (https://github.com/JohnEarnest/Octo) that
+// targets a plain CHIP-8: its statements, labels, :const, :alias, :unpack,
+// :byte, :org and :call, if/then, if/begin/else/end and loop/while/again.
+// macros, :calc, :stringmode and the SCHIP and XO-CHIP instructions aren't
+// understood, and neither are expressions beyond a number, constant or label
+
+const START: usize = 0x200;
+const END:   usize = 0x1000;
+const TEMP:  u8    = 0xf;   // register comparisons use, as Octo's "compare-temp"
+
+// statement and operand words, which can't name labels, constants or aliases
+const KEYWORDS: [&str; 23] = [
+    "clear", "return", "jump", "jump0", "native", "sprite", "bcd", "save", "load", "delay", "buzzer",
+    "i", "if", "then", "begin", "else", "end", "loop", "while", "again", "key", "hex", "random",
+];
+
+fn bad(line: usize, what: String) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("line {}: {}", line, what))
+}
+
+// the addresses a label fills in, once it's known
+#[derive(Clone, Copy)]
+enum Fixup {
+    Addr(usize),            // the nnn of the instruction at this offset
+    Unpack(usize, u8),      // :unpack's two loads at this offset, with the high nybble
+}
+
+// compiles Octo source to a program loaded at 0x200
+pub fn compile(src: &str) -> Result<Vec<u8>, Error> {
+    let tokens = src.lines().enumerate().flat_map(|(n, l)| {
+        l.split('#').next().unwrap_or("").split_whitespace().map(move |t| (n + 1, t))
+    }).collect();
+    let mut c = Compiler {
+        tokens,
+        pos:        0,
+        line:       1,
+        rom:        vec![0, 0],
+        jump_main:  true,
+        labels:     BTreeMap::new(),
+        consts:     BTreeMap::new(),
+        aliases:    BTreeMap::new(),
+        fixups:     Vec::new(),
+        blocks:     Vec::new(),
+        loops:      Vec::new(),
+    };
+    while c.pos < c.tokens.len() {
+        c.statement()?;
+    }
+    c.finish()
+}
+
+struct Compiler<'a> {
+    tokens:     Vec<(usize, &'a str)>,              // with their line numbers
+    pos:        usize,
+    line:       usize,                              // of the last token read
+    rom:        Vec<u8>,
+    jump_main:  bool,                               // the first two bytes are a jump to main
+    labels:     BTreeMap<&'a str, usize>,
+    consts:     BTreeMap<&'a str, usize>,
+    aliases:    BTreeMap<&'a str, u8>,
+    fixups:     Vec<(&'a str, usize, Fixup)>,       // label, line, what to fill in
+    blocks:     Vec<usize>,                         // begin and else jumps waiting for their target
+    loops:      Vec<(usize, Vec<usize>)>,           // loop addresses, with their whiles' jumps out
+}
+
+impl<'a> Compiler<'a> {
+    fn here(&self) -> usize {
+        START + self.rom.len()
+    }
+
+    fn err<T>(&self, what: String) -> Result<T, Error> {
+        Err(bad(self.line, what))
+    }
+
+    fn next(&mut self) -> Result<&'a str, Error> {
+        match self.tokens.get(self.pos) {
+            Some(&(line, t)) => {
+                self.pos += 1;
+                self.line = line;
+                Ok(t)
+            },
+            None => self.err("unexpected end of program".to_string()),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|t| t.1)
+    }
+
+    fn expect(&mut self, want: &str) -> Result<(), Error> {
+        match self.next()? {
+            t if t == want => Ok(()),
+            t => self.err(format!("expected \"{}\", found \"{}\"", want, t)),
+        }
+    }
+
+    fn inst(&mut self, a: u8, b: u8) {
+        self.rom.extend([a, b]);
+    }
+
+    // an instruction with an address, filled in later if it's a label not yet seen
+    fn inst_addr(&mut self, op: u8) -> Result<(), Error> {
+        let t = self.next()?;
+        let a = self.address(t, Fixup::Addr(self.rom.len()))?;
+        self.inst(op << 4 | (a >> 8) as u8, a as u8);
+        Ok(())
+    }
+
+    fn register(&mut self) -> Result<u8, Error> {
+        let t = self.next()?;
+        match register(t).or_else(|| self.aliases.get(t).copied()) {
+            Some(r) => Ok(r),
+            None    => self.err(format!("expected a register, found \"{}\"", t)),
+        }
+    }
+
+    fn is_register(&self) -> bool {
+        self.peek().is_some_and(|t| register(t).is_some() || self.aliases.contains_key(t))
+    }
+
+    // a number or constant
+    fn value(&self, t: &str) -> Option<i64> {
+        number(t).or_else(|| self.consts.get(t).map(|v| *v as i64))
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let t = self.next()?;
+        match self.value(t) {
+            Some(v) if (-128..=255).contains(&v) => Ok(v as u8),
+            Some(v) => self.err(format!("{} doesn't fit in a byte", v)),
+            None    => self.err(format!("expected a number, found \"{}\"", t)),
+        }
+    }
+
+    fn nybble(&mut self) -> Result<u8, Error> {
+        let t = self.next()?;
+        match self.value(t) {
+            Some(v) if (0..=15).contains(&v) => Ok(v as u8),
+            _ => self.err(format!("expected a number from 0 to 15, found \"{}\"", t)),
+        }
+    }
+
+    // a number, constant or label, with a label not yet seen filled in later
+    fn address(&mut self, t: &'a str, fixup: Fixup) -> Result<usize, Error> {
+        match self.value(t).or_else(|| self.labels.get(t).map(|a| *a as i64)) {
+            Some(a) if (0..END as i64).contains(&a) => Ok(a as usize),
+            Some(a) => self.err(format!("address {:#x} is out of range", a)),
+            None if name(t) => {
+                self.fixups.push((t, self.line, fixup));
+                Ok(0)
+            },
+            None => self.err(format!("expected an address, found \"{}\"", t)),
+        }
+    }
+
+    fn define(&mut self, t: &'a str) -> Result<(), Error> {
+        if !name(t) || register(t).is_some() || KEYWORDS.contains(&t) {
+            return self.err(format!("\"{}\" can't be a name", t));
+        }
+        if self.labels.contains_key(t) || self.consts.contains_key(t) || self.aliases.contains_key(t) {
+            return self.err(format!("\"{}\" is already defined", t));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), Error> {
+        let t = self.next()?;
+        match t {
+            ":" => {
+                let l = self.next()?;
+                self.define(l)?;
+                // main straight away needs no jump to it
+                if l == "main" && self.jump_main && self.rom.len() == 2 {
+                    self.rom.clear();
+                    self.jump_main = false;
+                }
+                self.labels.insert(l, self.here());
+            },
+            ":const" => {
+                let n = self.next()?;
+                self.define(n)?;
+                let v = self.next()?;
+                match self.value(v).or_else(|| self.labels.get(v).map(|a| *a as i64)) {
+                    Some(v) if v >= 0 => { self.consts.insert(n, v as usize); },
+                    _ => return self.err(format!("expected a number, found \"{}\"", v)),
+                }
+            },
+            ":alias" => {
+                let n = self.next()?;
+                self.define(n)?;
+                let r = self.register()?;
+                self.aliases.insert(n, r);
+            },
+            ":unpack" => {
+                let hi = self.nybble()?;
+                let l = self.next()?;
+                let a = self.address(l, Fixup::Unpack(self.rom.len(), hi))?;
+                self.inst(0x60, hi << 4 | (a >> 8) as u8);
+                self.inst(0x61, a as u8);
+            },
+            ":byte" => {
+                let b = self.byte()?;
+                self.rom.push(b);
+            },
+            ":org" => {
+                let l = self.next()?;
+                match self.value(l) {
+                    Some(a) if a as usize >= self.here() && (a as usize) < END => self.rom.resize(a as usize - START, 0),
+                    _ => return self.err(format!("can't move to {}", l)),
+                }
+            },
+            ":call" => self.inst_addr(0x2)?,
+            // debugger hints, which compile to nothing
+            ":breakpoint" => { self.next()?; },
+            ":monitor" => { self.next()?; self.next()?; },
+            "clear"  => self.inst(0x00, 0xe0),
+            "return" | ";" => self.inst(0x00, 0xee),
+            "jump"   => self.inst_addr(0x1)?,
+            "jump0"  => self.inst_addr(0xb)?,
+            "native" => self.inst_addr(0x0)?,
+            "sprite" => {
+                let (x, y, n) = (self.register()?, self.register()?, self.nybble()?);
+                self.inst(0xd0 | x, y << 4 | n);
+            },
+            "bcd"  => { let x = self.register()?; self.inst(0xf0 | x, 0x33) },
+            "save" => { let x = self.register()?; self.inst(0xf0 | x, 0x55) },
+            "load" => { let x = self.register()?; self.inst(0xf0 | x, 0x65) },
+            "delay" | "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.inst(0xf0 | x, if t == "delay" { 0x15 } else { 0x18 });
+            },
+            "i" => match self.next()? {
+                ":=" if self.peek() == Some("hex") => {
+                    self.next()?;
+                    let x = self.register()?;
+                    self.inst(0xf0 | x, 0x29);
+                },
+                ":=" => self.inst_addr(0xa)?,
+                "+=" => { let x = self.register()?; self.inst(0xf0 | x, 0x1e) },
+                o => return self.err(format!("unknown operator \"{}\" for i", o)),
+            },
+            "if" => {
+                let cond = self.pos;
+                // skip the condition to find how it's used
+                while !matches!(self.peek(), Some("then") | Some("begin") | None) { self.pos += 1; }
+                let begin = self.next()? == "begin";
+                let after = self.pos;
+                self.pos = cond;
+                self.condition(begin)?;
+                if self.pos != after - 1 {
+                    return self.err(format!("expected \"then\" or \"begin\", found \"{}\"", self.tokens[self.pos].1));
+                }
+                self.pos = after;
+                if begin {
+                    self.blocks.push(self.rom.len());
+                    self.inst(0x10, 0);
+                }
+            },
+            "else" => {
+                let b = self.blocks.pop().ok_or_else(|| bad(self.line, "else without begin".to_string()))?;
+                self.blocks.push(self.rom.len());
+                self.inst(0x10, 0);
+                self.patch(b, self.here());
+            },
+            "end" => {
+                let b = self.blocks.pop().ok_or_else(|| bad(self.line, "end without begin".to_string()))?;
+                self.patch(b, self.here());
+            },
+            "loop" => self.loops.push((self.here(), Vec::new())),
+            "while" => {
+                if self.loops.is_empty() { return self.err("while outside a loop".to_string()); }
+                self.condition(true)?;
+                let at = self.rom.len();
+                if let Some(l) = self.loops.last_mut() { l.1.push(at); }
+                self.inst(0x10, 0);
+            },
+            "again" => {
+                let (start, whiles) = self.loops.pop().ok_or_else(|| bad(self.line, "again without loop".to_string()))?;
+                self.inst(0x10 | (start >> 8) as u8, start as u8);
+                for w in whiles { self.patch(w, self.here()); }
+            },
+            _ if register(t).is_some() || self.aliases.contains_key(t) => {
+                self.pos -= 1;
+                self.assignment()?;
+            },
+            _ if self.value(t).is_some() => {
+                self.pos -= 1;
+                let b = self.byte()?;
+                self.rom.push(b);
+            },
+            _ if t.starts_with(':') => return self.err(format!("{} isn't supported: export a .ch8 from Octo instead", t)),
+            // anything else is a subroutine to call
+            _ => {
+                self.pos -= 1;
+                self.inst_addr(0x2)?;
+            },
+        }
+        if self.here() > END {
+            return self.err("the program is too big".to_string());
+        }
+        Ok(())
+    }
+
+    // vx op ...
+    fn assignment(&mut self) -> Result<(), Error> {
+        let x = self.register()?;
+        let op = self.next()?;
+        if self.is_register() {
+            let y = self.register()?;
+            let n = match op {
+                ":=" => 0x0, "|=" => 0x1, "&=" => 0x2, "^=" => 0x3, "+=" => 0x4,
+                "-=" => 0x5, ">>=" => 0x6, "=-" => 0x7, "<<=" => 0xe,
+                _ => return self.err(format!("unknown operator \"{}\" between registers", op)),
+            };
+            self.inst(0x80 | x, y << 4 | n);
+            return Ok(());
+        }
+        match (op, self.peek()) {
+            (":=", Some("random")) => { self.next()?; let n = self.byte()?; self.inst(0xc0 | x, n) },
+            (":=", Some("delay"))  => { self.next()?; self.inst(0xf0 | x, 0x07) },
+            (":=", Some("key"))    => { self.next()?; self.inst(0xf0 | x, 0x0a) },
+            (":=", _) => { let n = self.byte()?; self.inst(0x60 | x, n) },
+            ("+=", _) => { let n = self.byte()?; self.inst(0x70 | x, n) },
+            ("-=", _) => { let n = self.byte()?; self.inst(0x70 | x, n.wrapping_neg()) },
+            _ => return self.err(format!("unknown operator \"{}\"", op)),
+        }
+        Ok(())
+    }
+
+    // a condition, compiled to skip what follows when it's false,
+    // or with negate when it's true
+    fn condition(&mut self, negate: bool) -> Result<(), Error> {
+        let x = self.register()?;
+        let op = self.next()?;
+        let op = match (op, negate) {
+            (o, false) => o,
+            ("==", true) => "!=", ("!=", true) => "==",
+            ("key", true) => "-key", ("-key", true) => "key",
+            (">", true) => "<=", ("<=", true) => ">",
+            ("<", true) => ">=", (">=", true) => "<",
+            (o, true) => o,
+        };
+        match op {
+            "key"  => self.inst(0xe0 | x, 0xa1),
+            "-key" => self.inst(0xe0 | x, 0x9e),
+            "==" | "!=" if self.is_register() => {
+                let y = self.register()?;
+                self.inst(if op == "==" { 0x90 } else { 0x50 } | x, y << 4);
+            },
+            "==" | "!=" => {
+                let n = self.byte()?;
+                self.inst(if op == "==" { 0x40 } else { 0x30 } | x, n);
+            },
+            ">" | "<" | ">=" | "<=" => self.compare(x, op)?,
+            o => return self.err(format!("unknown comparison \"{}\"", o)),
+        }
+        Ok(())
+    }
+
+    // orderings subtract in vf and test its borrow flag
+    fn compare(&mut self, x: u8, op: &str) -> Result<(), Error> {
+        if self.is_register() {
+            let y = self.register()?;
+            self.inst(0x80 | TEMP, y << 4);
+        } else {
+            let n = self.byte()?;
+            self.inst(0x60 | TEMP, n);
+        }
+        let (sub, skip) = match op {
+            ">"  => (0x5, 0x30),
+            "<"  => (0x7, 0x30),
+            ">=" => (0x7, 0x40),
+            _    => (0x5, 0x40),
+        };
+        self.inst(0x80 | TEMP, x << 4 | sub);
+        self.inst(skip | TEMP, 1);
+        Ok(())
+    }
+
+    // sets the address of the jump at offset at
+    fn patch(&mut self, at: usize, addr: usize) {
+        self.rom[at] = self.rom[at] & 0xf0 | (addr >> 8) as u8;
+        self.rom[at + 1] = addr as u8;
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, Error> {
+        if !self.blocks.is_empty() { return self.err("begin without end".to_string()); }
+        if !self.loops.is_empty()  { return self.err("loop without again".to_string()); }
+        let main = *self.labels.get("main").ok_or_else(|| Error::new(ErrorKind::InvalidData, "no main label"))?;
+        if self.jump_main {
+            self.rom[0] = 0x10;
+            self.patch(0, main);
+        }
+        for (l, line, f) in std::mem::take(&mut self.fixups) {
+            let a = *self.labels.get(l).ok_or_else(|| bad(line, format!("undefined label \"{}\"", l)))?;
+            match f {
+                Fixup::Addr(at) => self.patch(at, a),
+                Fixup::Unpack(at, hi) => {
+                    self.rom[at + 1] = hi << 4 | (a >> 8) as u8;
+                    self.rom[at + 3] = a as u8;
+                },
+            }
+        }
+        Ok(self.rom)
+    }
+}
+
+fn register(t: &str) -> Option<u8> {
+    let r = t.strip_prefix('v').or_else(|| t.strip_prefix('V'))?;
+    if r.len() != 1 { return None; }
+    u8::from_str_radix(r, 16).ok()
+}
+
+// decimal, 0x hex or 0b binary, possibly negative
+fn number(t: &str) -> Option<i64> {
+    let (neg, t) = match t.strip_prefix('-') {
+        Some(t) => (true, t),
+        None    => (false, t),
+    };
+    let n = if let Some(h) = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")) {
+        i64::from_str_radix(h, 16).ok()
+    } else if let Some(b) = t.strip_prefix("0b").or_else(|| t.strip_prefix("0B")) {
+        i64::from_str_radix(b, 2).ok()
+    } else if t.starts_with(|c: char| c.is_ascii_digit()) {
+        t.parse().ok()
+    } else {
+        None
+    }?;
+    Some(if neg { -n } else { n })
+}
+
+// a label, constant or alias name
+fn name(t: &str) -> bool {
+    t.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && t.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+#[path = "test/octo_test.rs"]
+pub mod octo_test;
@@ -0,0 +1,159 @@
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind},
+};
+
+// IPS records can't start at the offset that spells its end marker
+const IPS_EOF:  usize = 0x45_4f46;
+const IPS_MAX:  usize = 0xffff;     // longest record
+
+fn bad(what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("patch: {}", what))
+}
+
+// apply an IPS or BPS patch, told apart by their headers
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.starts_with(b"PATCH") {
+        ips(rom, patch)
+    } else if patch.starts_with(b"BPS1") {
+        bps(rom, patch)
+    } else {
+        Err(bad("not an IPS or BPS patch"))
+    }
+}
+
+// IPS: records of (24-bit offset, 16-bit size, data), where a zero size is a
+// run of one repeated byte, then "EOF" and optionally a 24-bit truncated length
+fn ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = rom.to_vec();
+    let mut pos = 5;
+    let mut take = |n: usize| -> Result<&[u8], Error> {
+        let b = patch.get(pos..pos + n).ok_or_else(|| bad("truncated IPS patch"))?;
+        pos += n;
+        Ok(b)
+    };
+    let be = |b: &[u8]| b.iter().fold(0, |n, x| n << 8 | *x as usize);
+    loop {
+        let head = take(3)?;
+        if head == b"EOF" { break; }
+        let offset = be(head);
+        let data = match be(take(2)?) {
+            0 => {
+                let n = be(take(2)?);
+                vec![take(1)?[0]; n]
+            },
+            n => take(n)?.to_vec(),
+        };
+        if out.len() < offset + data.len() { out.resize(offset + data.len(), 0); }
+        out[offset..offset + data.len()].copy_from_slice(&data);
+    }
+    if let Ok(len) = take(3) {
+        out.truncate(be(len));
+    }
+    Ok(out)
+}
+
+// BPS: a list of copy actions from the source, target or patch, checked with
+// CRC32s of the source, target and patch itself
+fn bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+    if patch.len() < 4 + 12 { return Err(bad("truncated BPS patch")); }
+    let (body, footer) = patch.split_at(patch.len() - 12);
+    let crc = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    if crc32(&patch[..patch.len() - 4]) != crc(&footer[8..]) { return Err(bad("BPS patch checksum mismatch")); }
+    if crc32(rom) != crc(&footer[..4]) { return Err(bad("BPS patch is for a different ROM")); }
+
+    let mut p = &body[4..];
+    let source_size = varint(&mut p)?;
+    let target_size = varint(&mut p)?;
+    let meta = varint(&mut p)?;
+    if source_size != rom.len() { return Err(bad("BPS patch is for a different ROM")); }
+    p = p.get(meta..).ok_or_else(|| bad("truncated BPS patch"))?;
+
+    let mut out = Vec::with_capacity(target_size);
+    let (mut src_rel, mut tgt_rel) = (0isize, 0isize);
+    while !p.is_empty() {
+        let data = varint(&mut p)?;
+        let len = (data >> 2) + 1;
+        match data & 3 {
+            // source read: the bytes at the same offset in the source
+            0 => {
+                let at = out.len();
+                out.extend_from_slice(rom.get(at..at + len).ok_or_else(|| bad("BPS source read out of range"))?);
+            },
+            // target read: bytes from the patch
+            1 => {
+                out.extend_from_slice(p.get(..len).ok_or_else(|| bad("truncated BPS patch"))?);
+                p = &p[len..];
+            },
+            // source or target copy, from a relative offset
+            kind => {
+                let d = varint(&mut p)?;
+                let delta = if d & 1 == 1 { -((d >> 1) as isize) } else { (d >> 1) as isize };
+                let rel = if kind == 2 { &mut src_rel } else { &mut tgt_rel };
+                *rel += delta;
+                for _ in 0..len {
+                    let at = usize::try_from(*rel).map_err(|_| bad("BPS copy out of range"))?;
+                    let b = if kind == 2 { rom.get(at) } else { out.get(at) };
+                    let b = *b.ok_or_else(|| bad("BPS copy out of range"))?;
+                    out.push(b);
+                    *rel += 1;
+                }
+            },
+        }
+    }
+    if out.len() != target_size { return Err(bad("BPS target size mismatch")); }
+    if crc32(&out) != crc(&footer[4..8]) { return Err(bad("BPS target checksum mismatch")); }
+    Ok(out)
+}
+
+// BPS variable length number, 7 bits at a time with an offset folded into
+// each continuation, advancing the slice
+fn varint(b: &mut &[u8]) -> Result<usize, Error> {
+    let (mut n, mut shift) = (0usize, 1usize);
+    loop {
+        let (x, rest) = b.split_first().ok_or_else(|| bad("truncated BPS patch"))?;
+        *b = rest;
+        n = n.checked_add((*x as usize & 0x7f) * shift).ok_or_else(|| bad("bad BPS number"))?;
+        if x & 0x80 != 0 { return Ok(n); }
+        shift <<= 7;
+        n += shift;
+    }
+}
+
+// IPS patch turning original into modified
+pub fn make_ips(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut out = b"PATCH".to_vec();
+    let mut a = 0;
+    while a < modified.len() {
+        if original.get(a) == Some(&modified[a]) { a += 1; continue; }
+        // a run of differences; can't start a record at the "EOF" offset
+        let start = if a == IPS_EOF { a - 1 } else { a };
+        let mut end = a;
+        while end < modified.len() && end - start < IPS_MAX && original.get(end) != Some(&modified[end]) { end += 1; }
+        out.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+        out.extend_from_slice(&((end - start) as u16).to_be_bytes());
+        out.extend_from_slice(&modified[start..end]);
+        a = end;
+    }
+    out.extend_from_slice(b"EOF");
+    if modified.len() < original.len() {
+        out.extend_from_slice(&(modified.len() as u32).to_be_bytes()[1..]);
+    }
+    out
+}
+
+// CRC-32 (IEEE), as used by BPS
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+#[path = "test/patch_test.rs"]
+mod patch_test;
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+};
+
+use crate::{
+    cpu::RAM_SIZE,
+    instruction::Instruction,
+};
+
+// rows shown in each section of the report
+const TOP: usize = 10;
+
+// execution statistics gathered while a ROM runs
+pub struct Profile {
+    hits:       Vec<u64>,                   // executions per address
+    kinds:      HashMap<&'static str, u64>, // executions per instruction kind
+    calls:      HashMap<usize, u64>,        // 2nnn calls per subroutine address
+    loops:      HashMap<(usize, usize), u64>, // backward jumps/skips, (target, source) -> count
+    returns:    u64,
+    waiting:    u64,                        // machine cycles spent halted in fx0a
+    cycles:     u64,
+    frames:     u64,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::new()
+    }
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Profile {
+            hits:       vec![0; RAM_SIZE],
+            kinds:      HashMap::new(),
+            calls:      HashMap::new(),
+            loops:      HashMap::new(),
+            returns:    0,
+            waiting:    0,
+            cycles:     0,
+            frames:     0,
+        }
+    }
+
+    // an instruction at pc was executed, moving the pc to next
+    pub fn instruction(&mut self, pc: usize, inst: &Instruction, next: usize) {
+        self.cycles += 1;
+        self.hits[pc % RAM_SIZE] += 1;
+        *self.kinds.entry(inst.kind()).or_insert(0) += 1;
+        match inst {
+            Instruction::Call(nnn) => *self.calls.entry(*nnn).or_insert(0) += 1,
+            Instruction::Return    => self.returns += 1,
+            _ => (),
+        }
+        // a jump back to or before itself closes a loop
+        if next <= pc && !matches!(inst, Instruction::Return | Instruction::Call(_)) {
+            *self.loops.entry((next, pc)).or_insert(0) += 1;
+        }
+    }
+
+    // a machine cycle spent waiting for a key
+    pub fn wait(&mut self) {
+        self.cycles += 1;
+        self.waiting += 1;
+    }
+
+    pub fn frame(&mut self) {
+        self.frames += 1;
+    }
+
+    // human readable report; ram is used to show the instruction at each address
+    pub fn report(&self, ram: &[u8]) -> String {
+        let executed = self.cycles - self.waiting;
+        let pct = |n: u64, of: u64| if of == 0 { 0.0 } else { 100.0 * n as f64 / of as f64 };
+        let at = |a: usize| -> String {
+            let op = (ram[a % RAM_SIZE] as u16) << 8 | ram[(a + 1) % RAM_SIZE] as u16;
+            Instruction::decode(op).map(|i| i.to_string()).unwrap_or_else(|| format!("{:04x}", op))
+        };
+
+        let mut s = String::new();
+        let _ = writeln!(s, "{} instructions over {} frames ({:.1} per frame)",
+                         executed, self.frames, executed as f64 / self.frames.max(1) as f64);
+        let _ = writeln!(s, "{} cycles waiting for a key ({:.1}%)", self.waiting, pct(self.waiting, self.cycles));
+
+        let _ = writeln!(s, "\nhot addresses\n  addr        count      %  instruction");
+        let mut hits: Vec<(usize, u64)> = self.hits.iter().cloned().enumerate().filter(|(_, n)| *n > 0).collect();
+        hits.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (a, n) in hits.iter().take(TOP) {
+            let _ = writeln!(s, "  {:03x}  {:>11} {:>6.2}  {}", a, n, pct(*n, executed), at(*a));
+        }
+
+        // a loop's cost is every instruction executed inside its body
+        let _ = writeln!(s, "\nhot loops\n  range     iterations  instructions      %");
+        let mut loops: Vec<((usize, usize), u64, u64)> = self.loops.iter()
+            .map(|(&(start, end), &n)| ((start, end), n, self.hits[start..=end].iter().sum()))
+            .collect();
+        loops.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        for ((start, end), n, cost) in loops.iter().take(TOP) {
+            let _ = writeln!(s, "  {:03x}-{:03x} {:>11} {:>13} {:>6.2}", start, end, n, cost, pct(*cost, executed));
+        }
+
+        let _ = writeln!(s, "\nsubroutines ({} returns)\n  addr        calls", self.returns);
+        let mut calls: Vec<(&usize, &u64)> = self.calls.iter().collect();
+        calls.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (a, n) in calls.iter().take(TOP) {
+            let _ = writeln!(s, "  {:03x}  {:>11}", a, n);
+        }
+
+        let _ = writeln!(s, "\ninstructions\n  kind        count      %");
+        let mut kinds: Vec<(&&str, &u64)> = self.kinds.iter().collect();
+        kinds.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (k, n) in kinds {
+            let _ = writeln!(s, "  {}  {:>11} {:>6.2}", k, n, pct(*n, executed));
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+#[path = "test/profile_test.rs"]
+mod profile_test;
@@ -0,0 +1,112 @@
+use std::io::{Error, ErrorKind};
+
+// platforms whose CHIP-8 behaviour ROMs were written against.
+// only the CHIP-8 instruction set is emulated: SCHIP and XO-CHIP select the
+// quirks those interpreters have, not their extra instructions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Platform {
+    Chip8,      // COSMAC VIP
+    Schip,      // SUPER-CHIP 1.1 on the HP48
+    XoChip,     // Octo
+}
+
+impl Platform {
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        match name.to_lowercase().replace('-', "").as_str() {
+            "chip8"  => Ok(Platform::Chip8),
+            "schip"  => Ok(Platform::Schip),
+            "xochip" => Ok(Platform::XoChip),
+            _        => Err(Error::new(ErrorKind::InvalidInput, format!("unknown platform \"{}\"", name))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8  => "chip8",
+            Platform::Schip  => "schip",
+            Platform::XoChip => "xochip",
+        }
+    }
+}
+
+// quirk names, in the order they're listed
+pub const NAMES: [&str; 5] = ["vf_reset", "memory", "shifting", "jumping", "clipping"];
+
+// behaviours that differ between interpreters, named as in Timendus' quirks test.
+// the default is this emulator's original behaviour, which matches none of the platforms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    pub vf_reset:   bool,   // 8xy1, 8xy2, 8xy3 clear vF
+    pub memory:     bool,   // fx55, fx65 leave I after the last register
+    pub shifting:   bool,   // 8xy6, 8xye shift vx in place, ignoring vy
+    pub jumping:    bool,   // bnnn jumps to xnn + vx rather than nnn + v0
+    pub clipping:   bool,   // sprites are clipped at the edges rather than wrapped
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks { vf_reset: false, memory: false, shifting: true, jumping: false, clipping: false }
+    }
+}
+
+impl Quirks {
+    pub fn platform(p: Platform) -> Self {
+        match p {
+            Platform::Chip8  => Quirks { vf_reset: true,  memory: true,  shifting: false, jumping: false, clipping: true },
+            Platform::Schip  => Quirks { vf_reset: false, memory: false, shifting: true,  jumping: true,  clipping: true },
+            Platform::XoChip => Quirks { vf_reset: false, memory: true,  shifting: false, jumping: false, clipping: false },
+        }
+    }
+
+    fn flag(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "vf_reset" => Some(&mut self.vf_reset),
+            "memory"   => Some(&mut self.memory),
+            "shifting" => Some(&mut self.shifting),
+            "jumping"  => Some(&mut self.jumping),
+            "clipping" => Some(&mut self.clipping),
+            _          => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, on: bool) -> Result<(), Error> {
+        let f = self.flag(name).ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("unknown quirk \"{}\"", name)))?;
+        *f = on;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> bool {
+        let mut q = *self;
+        q.flag(name).is_some_and(|f| *f)
+    }
+
+    // comma separated overrides: "shifting,-clipping" turns one on and the other off
+    pub fn apply(&mut self, list: &str) -> Result<(), Error> {
+        for q in list.split(',').map(str::trim).filter(|q| !q.is_empty()) {
+            match q.strip_prefix('-') {
+                Some(q) => self.set(q, false)?,
+                None    => self.set(q.strip_prefix('+').unwrap_or(q), true)?,
+            }
+        }
+        Ok(())
+    }
+
+    // every quirk, as apply() reads them: "vf_reset,memory,-shifting,-jumping,clipping"
+    pub fn list(&self) -> String {
+        NAMES.iter()
+            .map(|q| if self.get(q) { q.to_string() } else { format!("-{}", q) })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    // the platform these quirks are, if any
+    pub fn name(&self) -> &'static str {
+        [Platform::Chip8, Platform::Schip, Platform::XoChip].iter()
+            .find(|p| Quirks::platform(**p) == *self)
+            .map_or(if *self == Quirks::default() { "default" } else { "custom" }, |p| p.name())
+    }
+}
+
+#[cfg(test)]
+#[path = "test/quirks_test.rs"]
+mod quirks_test;
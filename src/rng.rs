@@ -0,0 +1,45 @@
+use std::io::{Error, ErrorKind};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// seed used when none is given, so test runs are reproducible
+pub const DEFAULT_SEED: u64 = 0;
+
+// random byte source for cxnn, swappable per CPU
+pub trait Random {
+    fn byte(&mut self) -> u8;
+    fn reseed(&mut self, seed: u64);
+    fn name(&self) -> &'static str;
+    fn clone_box(&self) -> Box<dyn Random>;     // for saved states
+}
+
+// general purpose generator, uniform over 0..=255
+#[derive(Clone)]
+pub struct Seeded {
+    rng: StdRng,
+}
+
+impl Seeded {
+    pub fn new(seed: u64) -> Self {
+        Seeded { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Random for Seeded {
+    fn byte(&mut self) -> u8 { self.rng.gen() }
+    fn reseed(&mut self, seed: u64) { self.rng = StdRng::seed_from_u64(seed); }
+    fn name(&self) -> &'static str { "std" }
+    fn clone_box(&self) -> Box<dyn Random> { Box::new(self.clone()) }
+}
+
+// construct a generator from its command line name
+pub fn from_name(name: &str, seed: u64) -> Result<Box<dyn Random>, Error> {
+    match name {
+        "std" => Ok(Box::new(Seeded::new(seed))),
+        _     => Err(Error::new(ErrorKind::InvalidInput, format!("unknown RNG \"{}\"", name))),
+    }
+}
+
+#[cfg(test)]
+#[path = "test/rng_test.rs"]
+mod rng_test;
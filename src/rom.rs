@@ -1,42 +1,106 @@
 use std::{
-    io::{Error, ErrorKind, Read},
-    fs::File,
+    io::{self, Error, ErrorKind, Read},
 };
 
-use crate::cpu::{PC_BASE, RAM_SIZE};
+use crate::{
+    archive,
+    cartridge::{self, Options},
+    cpu::{PC_BASE, RAM_SIZE},
+    patch,
+};
 
 // maximum available program memory
 const ROM_SIZE: usize = RAM_SIZE - PC_BASE; 
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct ROM {
     pub rom:    [u8; ROM_SIZE],
+    size:       usize,              // bytes of program, the rest is padding
+    options:    Option<Options>,    // saved with the program in an Octo cartridge
 }
 
 impl ROM {
-    pub fn new_file(path: &str) -> Self {
-        let mut f = File::open(path).expect("invalid path");
-        let mut rom = [0; ROM_SIZE];
-        f.read(&mut rom).expect("file to buffer");
+    // load a binary or hex text ROM, `-` reading it from stdin and
+    // `archive.zip:game.ch8` from an archive
+    pub fn new_file(path: &str) -> Result<Self, Error> {
+        let data = if path == "-" {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf).map(|_| buf)
+        } else {
+            archive::read(path)
+        };
+        data.and_then(|d| ROM::new_data(&d)).map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))
+    }
 
-        ROM {
-            rom: rom,
+    // a ROM file's contents: the program itself, hex text or an Octo cartridge
+    pub fn new_data(d: &[u8]) -> Result<Self, Error> {
+        if d.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "empty ROM"));
+        }
+        if cartridge::is_cartridge(d) {
+            let (p, options) = cartridge::load(d)?;
+            return ROM::new_prog(&p).map(|r| ROM { options: Some(options), ..r });
+        }
+        match hex_text(d) {
+            Some(p) => ROM::new_prog(&p),
+            None    => ROM::new_prog(d),
         }
     }
 
-    #[allow(dead_code)] // initialize from hard-codeded vector, used in testing
+    // length of the program, without the padding after it
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    // SHA-1 of the program, identifying a ROM regardless of its file name
+    pub fn sha1(&self) -> String {
+        sha1_smol::Sha1::from(&self.rom[..self.size]).digest().to_string()
+    }
+
+    // options from an Octo cartridge
+    pub fn options(&self) -> Option<&Options> {
+        self.options.as_ref()
+    }
+
+    // apply an IPS or BPS patch to the program
+    pub fn patch(&self, p: &[u8]) -> Result<Self, Error> {
+        let r = ROM::new_prog(&patch::apply(&self.rom[..self.size], p)?)?;
+        Ok(ROM { options: self.options.clone(), ..r })
+    }
+
+    // initialize from a vector (hard-coded in testing, or patched)
     pub fn new_prog(p: &[u8]) -> Result<Self, Error> {
         if p.len() <= ROM_SIZE {
             let mut buf = [0; RAM_SIZE - PC_BASE];
-            for ii in 0..p.len() { buf[ii] = p[ii]; }
+            buf[..p.len()].copy_from_slice(p);
             Ok(ROM {
                 rom: buf,
+                size: p.len(),
+                options: None,
             })
         }
         else {
-            Err(Error::new(ErrorKind::InvalidData, "ROM program exceeds available memory!"))
+            Err(Error::new(ErrorKind::InvalidData,
+                format!("ROM program exceeds available memory! ({} bytes, {} available)", p.len(), ROM_SIZE)))
+        }
+    }
+}
+
+// bytes written out as hex, as ROMs are often shared on forums:
+// whitespace or comma separated, optionally 0x prefixed, and either single
+// bytes or whole instructions ("00e0 a22a" or "00 e0 a2 2a")
+fn hex_text(d: &[u8]) -> Option<Vec<u8>> {
+    let s = std::str::from_utf8(d).ok()?;
+    let mut out = Vec::new();
+    for t in s.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()) {
+        let t = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")).unwrap_or(t);
+        if t.is_empty() || t.len() % 2 != 0 || !t.bytes().all(|b| b.is_ascii_hexdigit()) { return None; }
+        for i in (0..t.len()).step_by(2) {
+            out.push(u8::from_str_radix(&t[i..i + 2], 16).ok()?);
         }
     }
+    if out.is_empty() { None } else { Some(out) }
 }
 
 #[cfg(test)]
@@ -0,0 +1,111 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    cartridge::Options,
+    keypad::Keymap,
+    quirks::{Platform, Quirks},
+};
+
+// entries for the test ROMs shipped in roms/ and nothing else: games are only
+// recognised with --romdb
+const BUILTIN: &str = include_str!("../roms/romdb.json");
+
+// what we know about a ROM:
+//
+//   "a9993e364706816aba3e25717850c26c9cd0d89d": {
+//     "title": "Breakout", "author": "...", "platform": "schip",
+//     "quirks": { "clipping": false }, "ipf": 15, "keymap": { "j": "4", "l": "6" }
+//   }
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    pub title:      String,
+    pub author:     String,
+    pub platform:   Option<Platform>,
+    pub quirks:     Quirks,         // the platform's, with the entry's overrides
+    pub ipf:        Option<usize>,  // machine cycles per frame
+    pub keymap:     Keymap,         // extra keys, keyboard character to CHIP-8 key
+}
+
+// ROM database, keyed by SHA-1 of the program
+pub struct RomDb {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl RomDb {
+    // the built-in entries, then those in a database file taking precedence
+    pub fn load(path: Option<&str>) -> Result<Self, Error> {
+        let mut entries = parse(BUILTIN).expect("built-in ROM database");
+        if let Some(p) = path {
+            let s = fs::read_to_string(p)?;
+            entries.extend(parse(&s).map_err(|e| Error::new(e.kind(), format!("{}: {}", p, e)))?);
+        }
+        Ok(RomDb { entries })
+    }
+
+    pub fn get(&self, sha1: &str) -> Option<&Entry> {
+        self.entries.get(sha1)
+    }
+}
+
+// quirks and cycles per frame for a ROM: its database entry's,
+// then the options saved in its cartridge
+pub fn settings(entry: Option<&Entry>, options: Option<&Options>) -> Result<(Quirks, Option<usize>), Error> {
+    let mut quirks = entry.map(|e| e.quirks).unwrap_or_default();
+    for (name, on) in options.map_or(&[][..], |o| &o.quirks[..]) { quirks.set(name, *on)?; }
+    let ipf = options.and_then(|o| o.ipf).or_else(|| entry.and_then(|e| e.ipf));
+    Ok((quirks, ipf))
+}
+
+pub fn parse(s: &str) -> Result<BTreeMap<String, Entry>, Error> {
+    let bad = |what: String| Error::new(ErrorKind::InvalidData, what);
+    let j: Value = serde_json::from_str(s).map_err(|e| bad(format!("invalid ROM database: {}", e)))?;
+    let roms = j.as_object().ok_or_else(|| bad("ROM database is not an object".to_string()))?;
+    roms.iter().map(|(sha, e)| {
+        let e = e.as_object().ok_or_else(|| bad(format!("{}: not an object", sha)))?;
+        entry(e).map(|e| (sha.to_lowercase(), e)).map_err(|err| bad(format!("{}: {}", sha, err)))
+    }).collect()
+}
+
+fn entry(e: &Map<String, Value>) -> Result<Entry, Error> {
+    let bad = |what: &str| Error::new(ErrorKind::InvalidData, what.to_string());
+    let text = |k: &str| e.get(k).and_then(Value::as_str).unwrap_or("").to_string();
+
+    let platform = match e.get("platform").map(|p| p.as_str().ok_or_else(|| bad("platform is not a string"))) {
+        Some(p) => Some(Platform::from_name(p?)?),
+        None    => None,
+    };
+    let mut quirks = platform.map(Quirks::platform).unwrap_or_default();
+    if let Some(q) = e.get("quirks") {
+        for (name, on) in q.as_object().ok_or_else(|| bad("quirks is not an object"))? {
+            quirks.set(name, on.as_bool().ok_or_else(|| bad("quirks are true or false"))?)?;
+        }
+    }
+    let ipf = match e.get("ipf") {
+        Some(n) => Some(n.as_u64().filter(|n| *n > 0).ok_or_else(|| bad("ipf is not a positive number"))? as usize),
+        None    => None,
+    };
+    let mut keymap = Keymap::new();
+    if let Some(k) = e.get("keymap") {
+        for (from, to) in k.as_object().ok_or_else(|| bad("keymap is not an object"))? {
+            let mut chars = from.chars();
+            let from = match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _               => return Err(bad("keymap keys are single characters")),
+            };
+            let to = to.as_str().and_then(|t| usize::from_str_radix(t, 16).ok()).filter(|t| *t < 16)
+                .ok_or_else(|| bad("keymap values are CHIP-8 keys 0-f"))?;
+            keymap.insert(from, to);
+        }
+    }
+    Ok(Entry { title: text("title"), author: text("author"), platform, quirks, ipf, keymap })
+}
+
+#[cfg(test)]
+#[path = "test/romdb_test.rs"]
+mod romdb_test;
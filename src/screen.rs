@@ -1,95 +1,352 @@
-use std::io;
+use std::{
+    cell::Cell,
+    io,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
-use termion::raw::{IntoRawMode,RawTerminal};
+use signal_hook::{SigId, consts::SIGWINCH, low_level};
+use termion::{
+    input::MouseTerminal,
+    raw::{IntoRawMode,RawTerminal},
+    screen::AlternateScreen,
+};
 use tui::{
+    Frame,
     Terminal,
-    backend::TermionBackend,
+    backend::{Backend, TermionBackend},
+    buffer::Buffer,
     widgets::{
         Block,
         Borders,
-        canvas::{
-            Canvas,
-            Points
-        },
-    },
-    style::Color,
-    symbols::Marker,
-    layout::{
-        Layout,
-        Constraint,
-        Direction
+        Paragraph,
+        Widget,
+        Wrap,
     },
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    layout::Rect,
 };
 
 pub const V_WIDTH:    usize = 64;
 pub const V_HEIGHT:   usize = 32;
 
+use crate::{
+    cast::{Cast, Recorder},
+    cpu::Cpu,
+    debugger::Debugger,
+    keypad::{GRID, LAYOUT},
+    menu::Menu,
+};
+
+// keypad cells, the key over the keyboard character for it
+const CELL_WIDTH:   u16 = 6;
+const CELL_HEIGHT:  u16 = 2;
+const PAD_WIDTH:    u16 = CELL_WIDTH * 4 + 2;
+const PAD_HEIGHT:   u16 = CELL_HEIGHT * 4 + 2;
+
+// room the debugger wants beside the display
+const DEBUGGER_WIDTH: u16 = 52;
+
+// renders a key stays lit after it's let go: presses
+// from the terminal only last a frame
+const LIT: u8 = 6;
+
+// display colours: lit pixels, the background, and the background while the buzzer sounds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub fg:     Color,
+    pub bg:     Color,
+    pub buzz:   Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette { fg: Color::White, bg: Color::Reset, buzz: Color::Reset }
+    }
+}
+
+// how the display is drawn: the largest that fits the terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Renderer {
+    Block(u16),     // a cell for each pixel, scaled up by a whole factor
+    HalfBlock,      // two pixels to a cell, one above the other
+    Braille,        // eight pixels to a cell
+}
+
+impl Renderer {
+    fn fit(width: u16, height: u16) -> Option<Self> {
+        let (w, h) = (V_WIDTH as u16, V_HEIGHT as u16);
+        let scale = (width / w).min(height / h);
+        if scale > 0 {
+            Some(Renderer::Block(scale))
+        } else if width >= w && height >= h / 2 {
+            Some(Renderer::HalfBlock)
+        } else if width >= w / 2 && height >= h / 4 {
+            Some(Renderer::Braille)
+        } else {
+            None
+        }
+    }
+
+    // cells taken by the display
+    fn size(self) -> (u16, u16) {
+        let (w, h) = (V_WIDTH as u16, V_HEIGHT as u16);
+        match self {
+            Renderer::Block(s)  => (w * s, h * s),
+            Renderer::HalfBlock => (w, h / 2),
+            Renderer::Braille   => (w / 2, h / 4),
+        }
+    }
+}
+
+// where everything goes in the terminal
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Areas {
+    renderer:   Renderer,
+    display:    Rect,           // including its border
+    pad:        Option<Rect>,
+    debugger:   Option<Rect>,
+    status:     Option<Rect>,
+}
+
+impl Areas {
+    // None when even the smallest display doesn't fit
+    fn new(size: Rect, debugger: bool) -> Option<Self> {
+        let area = Rect {
+            x: size.x + 1, y: size.y + 1,
+            width: size.width.saturating_sub(2), height: size.height.saturating_sub(2),
+        };
+        let main = Rect { height: area.height.saturating_sub(1), ..area };
+        let fit = |reserve: u16| Renderer::fit(main.width.saturating_sub(2 + reserve), main.height.saturating_sub(2));
+        // the debugger is left out rather than the display
+        let (renderer, debugger) = match fit(if debugger { DEBUGGER_WIDTH } else { 0 }) {
+            Some(r) => (r, debugger),
+            None    => (fit(0)?, false),
+        };
+        let (w, h) = renderer.size();
+        let display = Rect { width: w + 2, height: h + 2, ..main };
+        let mut rest = Rect { x: display.right(), width: main.right() - display.right(), ..main };
+        let reserve = if debugger { DEBUGGER_WIDTH } else { 0 };
+        let pad = if rest.width >= PAD_WIDTH + reserve && rest.height >= PAD_HEIGHT {
+            let pad = Rect { width: PAD_WIDTH, height: PAD_HEIGHT, ..rest };
+            rest = Rect { x: pad.right(), width: rest.width - PAD_WIDTH, ..rest };
+            Some(pad)
+        } else {
+            None
+        };
+        Some(Areas {
+            renderer,
+            display,
+            pad,
+            debugger: Some(rest).filter(|_| debugger),
+            status: Some(Rect { y: main.bottom(), height: 1, ..area }).filter(|_| area.height > main.height),
+        })
+    }
+}
+
+// the CHIP-8's display, drawn with a renderer
+struct Display<'a> {
+    vram:       &'a [[u8; V_HEIGHT]; V_WIDTH],
+    renderer:   Renderer,
+    fg:         Color,
+    bg:         Color,
+}
+
+impl Display<'_> {
+    fn lit(&self, x: usize, y: usize) -> bool {
+        self.vram[x][y] == 1
+    }
+
+    // the character for a cell
+    fn cell(&self, cx: usize, cy: usize) -> char {
+        match self.renderer {
+            Renderer::Block(s) => {
+                let s = s as usize;
+                if self.lit(cx / s, cy / s) { '█' } else { ' ' }
+            },
+            Renderer::HalfBlock => match (self.lit(cx, cy * 2), self.lit(cx, cy * 2 + 1)) {
+                (true, true)   => '█',
+                (true, false)  => '▀',
+                (false, true)  => '▄',
+                (false, false) => ' ',
+            },
+            Renderer::Braille => {
+                // dot bits by row, left column then right
+                const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+                let mut bits = 0;
+                for (dy, row) in DOTS.iter().enumerate() {
+                    for (dx, bit) in row.iter().enumerate() {
+                        if self.lit(cx * 2 + dx, cy * 4 + dy) { bits |= bit; }
+                    }
+                }
+                // safe unwrap: the braille block is all valid characters
+                std::char::from_u32(0x2800 + bits).unwrap()
+            },
+        }
+    }
+}
+
+impl Widget for Display<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let (w, h) = self.renderer.size();
+        for cy in 0..h.min(area.height) {
+            for cx in 0..w.min(area.width) {
+                buf.get_mut(area.x + cx, area.y + cy)
+                    .set_char(self.cell(cx as usize, cy as usize))
+                    .set_fg(self.fg)
+                    .set_bg(self.bg);
+            }
+        }
+    }
+}
+
+type Term = Terminal<TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<Recorder<io::Stdout>>>>>>;
+
 pub struct Screen {
     enable:         bool,
-    term:           Terminal<TermionBackend<RawTerminal<io::Stdout>>>,
-    chunks:         tui::layout::Layout,
+    term:           Term,
+    redraw:         Arc<AtomicBool>,    // SIGWINCH or a suspend since the last render
+    winch:          SigId,              // the SIGWINCH handler setting redraw
+    clock:          Rc<Cell<f64>>,      // emulated time of the frame being drawn
+    palette:        Palette,
+    status:         String,             // shown below the display
+    pad:            Rect,               // where the keypad's keys were drawn, for clicks
+    lit:            [u8; 16],           // renders each key stays highlighted
 }
 
 impl Screen {
-    pub fn new(enable: bool) -> Result<Option<Self>, io::Error> {
+    // cast: optionally record everything written to the terminal as an asciinema file.
+    // the terminal is given back as it was when the Screen is dropped
+    pub fn new(enable: bool, cast: Option<&str>) -> Result<Option<Self>, io::Error> {
         if !enable { return Ok(None) }
-        let stdout = io::stdout().into_raw_mode()?;
-        let backend = TermionBackend::new(stdout);
+        let clock = Rc::new(Cell::new(0.0));
+        let cast = match cast {
+            None    => None,
+            Some(p) => {
+                let (w, h) = termion::terminal_size()?;
+                Some(Cast::create(p, w, h)?)
+            },
+        };
+        let raw = Recorder::new(io::stdout(), cast, clock.clone()).into_raw_mode()?;
+        let backend = TermionBackend::new(AlternateScreen::from(MouseTerminal::from(raw)));
         let mut term = Terminal::new(backend)?;
         term.clear()?;
-
-        // layout constraints and configuration
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints(
-                [
-                    Constraint::Length(V_HEIGHT as u16 + 2)
-                ].as_ref()
-            );
+        let redraw = Arc::new(AtomicBool::new(false));
+        let winch = signal_hook::flag::register(SIGWINCH, redraw.clone())?;
 
         Ok(Some(Screen {
-            enable: enable,
-            term:   term,
-            chunks: chunks,
+            enable,
+            term,
+            redraw,
+            winch,
+            clock,
+            palette: Palette::default(),
+            status: String::new(),
+            pad: Rect::default(),
+            lit: [0; 16],
         }))
     }
 
-    // TODO: pass vram as compile-time constant &[&[u8]]
-    pub fn render(&mut self, pixels: &[[u8; V_HEIGHT]; V_WIDTH]) {
-        // I hope I can come back to this and think:
-        // "man, I *was* bad at Rust"
-        //
-        // - self.chunks inside the |f| closure
-        // - move canvas into Screen struct
-        if !self.enable { return; }
-        let chunks = self.chunks.clone();
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    // draw everything afresh next time, when the terminal may have been written over
+    pub fn redraw(&mut self) {
+        self.redraw.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = status;
+    }
+
+    // the key drawn at a clicked position (termion's, from 1)
+    pub fn key_at(&self, x: u16, y: u16) -> Option<usize> {
+        key_at(self.pad, x.checked_sub(1)?, y.checked_sub(1)?)
+    }
+
+    // draw the display and keypad, the debugger beside them when open, and the menu over all,
+    // laid out afresh each time for the terminal's size
+    pub fn render(&mut self, c: &Cpu, mut dbg: Option<&mut Debugger>, menu: Option<&Menu>) -> io::Result<()> {
+        if !self.enable { return Ok(()); }
+        // tui follows the new size by itself; clearing also gets rid of
+        // whatever the terminal left behind resizing
+        if self.redraw.swap(false, Ordering::Relaxed) { self.term.clear()?; }
+        self.clock.set(c.clock()); // timestamp recorded output with the emulated time
+        let p = self.palette;
+        let status = &self.status;
+        for (l, down) in self.lit.iter_mut().zip(c.keys().iter()) {
+            *l = if *down { LIT } else { l.saturating_sub(1) };
+        }
+        let lit = self.lit;
+        let mut pad = Rect::default();
+        let bg = if c.buzzing() { p.buzz } else { p.bg };
+        let debugging = dbg.as_ref().is_some_and(|d| d.open);
         self.term.draw(|f| {
-            let canvas =
-                Canvas::default()
-                .block(Block::default().title("CHIP8").borders(Borders::ALL))
-                .marker(Marker::Block)
-                .x_bounds([0.0, V_WIDTH  as f64])
-                .y_bounds([0.0, V_HEIGHT as f64])
-                .paint(|ctx| {
-                    let mut pts: Vec<(f64, f64)> = Vec::new();
-                    for ii in 0..pixels.len() {
-                        for jj in 0..pixels[ii].len() {
-                            let px = ii as f64;
-                            // convert Canvas y coordinate to graphics coordinates
-                            let py = (V_HEIGHT as f64 - jj as f64).abs();
-                            if pixels[ii][jj] == 1 { pts.push((px, py)) };
-                        }
-                    }
+            let areas = match Areas::new(f.size(), debugging) {
+                Some(a) => a,
+                None    => return too_small(f),
+            };
+            let block = Block::default().title("CHIP8").borders(Borders::ALL);
+            let inner = block.inner(areas.display);
+            f.render_widget(block, areas.display);
+            f.render_widget(Display { vram: &c.vram, renderer: areas.renderer, fg: p.fg, bg }, inner);
+            if let Some(a) = areas.pad { pad = draw_keypad(f, a, &lit); }
+            if let (Some(d), Some(a)) = (dbg.as_mut(), areas.debugger) { d.draw(f, a, c); }
+            if let Some(a) = areas.status { f.render_widget(Paragraph::new(status.as_str()), a); }
+            let main = areas.display.union(areas.pad.unwrap_or(areas.display)).union(areas.debugger.unwrap_or(areas.display));
+            if let Some(m) = menu.filter(|m| m.open) { m.draw(f, main, c); }
+        })?;
+        self.pad = pad;
+        Ok(())
+    }
+}
 
-                    ctx.draw(&Points {
-                        coords: &pts,
-                        color: Color::White,
-                    });
-                });
+impl Drop for Screen {
+    fn drop(&mut self) {
+        low_level::unregister(self.winch);
+    }
+}
+
+// in place of everything when the smallest display won't fit
+fn too_small<B: Backend>(f: &mut Frame<B>) {
+    // the display's border and the margin around everything, and the status line
+    let (w, h) = Renderer::Braille.size();
+    let text = format!("terminal too small: needs {}x{}", w + 4, h + 5);
+    f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), f.size());
+}
 
-            let chunks = chunks.split(f.size());
-            f.render_widget(canvas, chunks[0]);
-        }).unwrap();
+// the CHIP-8 keypad, lighting the keys held; returns the area of its keys
+fn draw_keypad<B: Backend>(f: &mut Frame<B>, area: Rect, lit: &[u8; 16]) -> Rect {
+    let area = Rect { height: PAD_HEIGHT.min(area.height), ..area };
+    let mut lines = Vec::new();
+    for row in GRID.iter() {
+        let style = |k: usize| if lit[k] > 0 { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() };
+        lines.push(Spans::from(row.iter().map(|k| {
+            Span::styled(format!("  {:X}   ", k), style(*k).add_modifier(Modifier::BOLD))
+        }).collect::<Vec<_>>()));
+        lines.push(Spans::from(row.iter().map(|k| {
+            Span::styled(format!("  {}   ", LAYOUT[*k]), style(*k).fg(Color::DarkGray))
+        }).collect::<Vec<_>>()));
     }
+    let block = Block::default().title("keypad").borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+    inner
 }
+
+// the key in a cell of the keypad's area
+fn key_at(pad: Rect, x: u16, y: u16) -> Option<usize> {
+    let (col, row) = ((x.checked_sub(pad.x)?) / CELL_WIDTH, (y.checked_sub(pad.y)?) / CELL_HEIGHT);
+    if x >= pad.x + pad.width || y >= pad.y + pad.height { return None; }
+    GRID.get(row as usize)?.get(col as usize).copied()
+}
+
+#[cfg(test)]
+#[path = "test/screen_test.rs"]
+mod screen_test;
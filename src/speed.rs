@@ -0,0 +1,97 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use termion::event::Key;
+
+use crate::cpu::{Cpu, TIMER_HZ};
+
+// frames run per tick while fast forwarding
+pub const FAST_FORWARD: usize = 4;
+
+// fast forward is held on by the terminal's key repeat, which only starts
+// after a delay: it stops this long after the last repeat
+const HOLD: Duration = Duration::from_millis(600);
+
+// falling this far behind (a slow terminal, a suspended process) starts
+// the ticks afresh rather than running frames to catch up
+const SLACK: Duration = Duration::from_millis(250);
+
+// emulation speed, and pacing the run loop to the 60 Hz frames:
+//   [ ]    fewer or more machine cycles per frame
+//   `      fast forward, while held
+//   \      half speed, toggled
+//   .      run one frame, while paused
+pub struct Speed {
+    half:       bool,               // a frame every other tick
+    odd:        bool,               // at half speed, this tick runs nothing
+    turbo:      Option<Instant>,    // fast forwarding until
+    advance:    bool,               // a frame to run while paused
+    locked:     bool,               // recording a movie, which doesn't hold cycles per frame
+    next:       Instant,            // when the next tick is due
+}
+
+impl Speed {
+    pub fn new(locked: bool) -> Self {
+        Speed { half: false, odd: false, turbo: None, advance: false, locked, next: Instant::now() }
+    }
+
+    // handle a key, returning false if it should go on to the keypad
+    pub fn key(&mut self, key: Key, c: &mut Cpu) -> bool {
+        match key {
+            Key::Char('[')  => self.set_ipf(c, c.ipf().saturating_sub(1)),
+            Key::Char(']')  => self.set_ipf(c, c.ipf() + 1),
+            Key::Char('`')  => self.turbo = Some(Instant::now() + HOLD),
+            Key::Char('\\') => self.half = !self.half,
+            Key::Char('.')  => self.advance = true,
+            _ => return false,
+        }
+        true
+    }
+
+    fn set_ipf(&self, c: &mut Cpu, ipf: usize) {
+        if !self.locked { c.set_ipf(ipf); }
+    }
+
+    fn fast(&self) -> bool {
+        self.turbo.is_some_and(|t| Instant::now() < t)
+    }
+
+    // frames to run this tick
+    pub fn frames(&mut self, paused: bool) -> usize {
+        if paused {
+            return if std::mem::take(&mut self.advance) { 1 } else { 0 };
+        }
+        self.advance = false;
+        if self.fast() { return FAST_FORWARD; }
+        self.odd = self.half && !self.odd;
+        if self.odd { 0 } else { 1 }
+    }
+
+    // sleep until the next tick is due
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        match self.next.checked_duration_since(now) {
+            Some(d) => thread::sleep(d),
+            None    => if now - self.next > SLACK { self.next = now; },
+        }
+        self.next += Duration::from_secs(1) / TIMER_HZ as u32;
+    }
+
+    // e.g. "10 cycles/frame ×½"
+    pub fn describe(&self, c: &Cpu) -> String {
+        let rate = if self.fast() {
+            format!(" ×{} ▸▸", FAST_FORWARD)
+        } else if self.half {
+            String::from(" ×½")
+        } else {
+            String::from(" ×1")
+        };
+        format!("{} cycles/frame{}", c.ipf(), rate)
+    }
+}
+
+#[cfg(test)]
+#[path = "test/speed_test.rs"]
+mod speed_test;
@@ -0,0 +1,54 @@
+use std::time::{Duration, Instant};
+
+use crate::cpu::Cpu;
+
+// how often the rates are measured
+const PERIOD: Duration = Duration::from_secs(1);
+
+// status line below the display:
+//   Breakout │ 600 ips  60 fps │ 10 cycles/frame │ paused │ ♪ │ chip8
+pub struct Status {
+    title:      String,
+    since:      Instant,        // start of the period being measured
+    counts:     (u64, u64),     // machine cycles and frames at its start
+    ips:        u64,            // instructions per second, over the last period
+    fps:        u64,            // frames per second
+}
+
+impl Status {
+    pub fn new(title: &str, c: &Cpu, now: Instant) -> Self {
+        Status { title: title.to_string(), since: now, counts: c.counts(), ips: 0, fps: 0 }
+    }
+
+    // after each tick, measuring the rates once a period is over
+    pub fn update(&mut self, c: &Cpu, now: Instant) {
+        let elapsed = now.duration_since(self.since);
+        if elapsed < PERIOD { return; }
+        let (cycles, frames) = c.counts();
+        // a reset or a loaded state can take the counts back
+        let rate = |n: u64, then: u64| (n.saturating_sub(then) as f64 / elapsed.as_secs_f64()).round() as u64;
+        self.ips = rate(cycles, self.counts.0);
+        self.fps = rate(frames, self.counts.1);
+        self.since = now;
+        self.counts = (cycles, frames);
+    }
+
+    pub fn line(&self, c: &Cpu, speed: &str, paused: bool) -> String {
+        let state = if paused {
+            "paused"
+        } else if c.waiting() {
+            "waiting for a key"
+        } else {
+            "running"
+        };
+        let sound = if c.buzzing() { "♪" } else { " " };
+        format!(
+            "{} │ {} ips {:>3} fps │ {} │ {} │ {} │ {}",
+            self.title, self.ips, self.fps, speed, state, sound, c.quirks().name(),
+        )
+    }
+}
+
+#[cfg(test)]
+#[path = "test/status_test.rs"]
+mod status_test;
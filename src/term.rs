@@ -0,0 +1,161 @@
+use std::{
+    cell::Cell,
+    io::{self, Error, ErrorKind, Write},
+    mem,
+    panic,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicI32, Ordering},
+    },
+};
+
+use signal_hook::{
+    SigId,
+    consts::{SIGCONT, SIGINT, SIGSTOP, SIGTERM, SIGTSTP},
+    low_level,
+};
+use termion::{cursor, screen::{ToAlternateScreen, ToMainScreen}};
+
+// mouse reporting on and off, as termion's MouseTerminal does
+const MOUSE_ON:  &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+// the terminal's settings before raw mode, as termion reads them from stdout
+static SAVED: OnceLock<libc::termios> = OnceLock::new();
+
+// the signal that ended emulation, to end the process with once cleaned up
+static CAUGHT: AtomicI32 = AtomicI32::new(0);
+
+thread_local! {
+    // a panic here is caught and reported some other way, so it leaves the terminal alone
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
+
+// note the terminal's settings, before anything changes them
+pub fn save() {
+    if !termion::is_tty(&io::stdout()) { return; }
+    // safe: tcgetattr only writes the termios it's given
+    unsafe {
+        let mut t: libc::termios = mem::zeroed();
+        if libc::tcgetattr(libc::STDOUT_FILENO, &mut t) == 0 { let _ = SAVED.set(t); }
+    }
+}
+
+// put the terminal back as it was: out of raw mode, the alternate screen and
+// mouse reporting, with the cursor showing. the Screen does this when it's
+// dropped, which is too late for anything printed before
+pub fn restore() {
+    let t = match SAVED.get() {
+        Some(t) => t,
+        None    => return,
+    };
+    let mut out = io::stdout();
+    let _ = write!(out, "{}{}{}", MOUSE_OFF, ToMainScreen, cursor::Show);
+    let _ = out.flush();
+    // safe: t was filled in by tcgetattr
+    unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, t); }
+}
+
+// back into raw mode and the alternate screen, after restore()
+fn enter() {
+    let mut t = match SAVED.get() {
+        Some(t) => *t,
+        None    => return,
+    };
+    // safe: t is a copy of what tcgetattr filled in
+    unsafe {
+        libc::cfmakeraw(&mut t);
+        libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, &t);
+    }
+    let mut out = io::stdout();
+    let _ = write!(out, "{}{}{}", ToAlternateScreen, MOUSE_ON, cursor::Hide);
+    let _ = out.flush();
+}
+
+// give the terminal back and stop, as ^Z would outside raw mode;
+// once continued, take it again. the screen needs drawing afresh
+pub fn suspend() {
+    restore();
+    let _ = low_level::raise(SIGSTOP);
+    enter();
+}
+
+// restore the terminal before a panic's message is printed
+pub fn install_panic_hook() {
+    save();
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if QUIET.with(Cell::get) { return; }
+        restore();
+        hook(info);
+    }));
+}
+
+// run f, with None if it panics, without touching the terminal or printing the panic
+pub fn catch<R>(f: impl FnOnce() -> R + panic::UnwindSafe) -> Option<R> {
+    QUIET.with(|q| q.set(true));
+    let r = panic::catch_unwind(f).ok();
+    QUIET.with(|q| q.set(false));
+    r
+}
+
+// the signal that ended emulation, if one did
+pub fn caught() -> Option<i32> {
+    Some(CAUGHT.load(Ordering::Relaxed)).filter(|s| *s != 0)
+}
+
+// end the process the way the caught signal would have
+pub fn reraise(sig: i32) -> ! {
+    let _ = low_level::emulate_default_handler(sig);
+    std::process::exit(128 + sig);
+}
+
+// SIGINT, SIGTERM, SIGTSTP and SIGCONT, while the terminal is in raw mode.
+// they're noted when they arrive, and acted on between frames
+pub struct Signals {
+    flags:  Vec<(i32, Arc<AtomicBool>)>,
+    ids:    Vec<SigId>,
+}
+
+impl Signals {
+    pub fn new() -> Result<Self, Error> {
+        let mut s = Signals { flags: Vec::new(), ids: Vec::new() };
+        for sig in [SIGINT, SIGTERM, SIGTSTP, SIGCONT].iter() {
+            let flag = Arc::new(AtomicBool::new(false));
+            s.ids.push(signal_hook::flag::register(*sig, flag.clone())?);
+            s.flags.push((*sig, flag));
+        }
+        Ok(s)
+    }
+
+    // act on the signals received: an error to stop for SIGINT and SIGTERM,
+    // otherwise whether the screen has to be drawn afresh
+    pub fn poll(&self) -> Result<bool, Error> {
+        let mut redraw = false;
+        for (sig, flag) in &self.flags {
+            if !flag.swap(false, Ordering::Relaxed) { continue; }
+            match *sig {
+                SIGTSTP => suspend(),
+                // stopped some other way: the shell may have changed the terminal
+                SIGCONT => if !redraw { enter() },
+                _ => {
+                    CAUGHT.store(*sig, Ordering::Relaxed);
+                    let name = if *sig == SIGINT { "SIGINT" } else { "SIGTERM" };
+                    return Err(Error::new(ErrorKind::Interrupted, format!("stopped by {}", name)));
+                },
+            }
+            redraw = true;
+        }
+        Ok(redraw)
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        for id in self.ids.drain(..) { low_level::unregister(id); }
+    }
+}
+
+#[cfg(test)]
+#[path = "test/term_test.rs"]
+mod term_test;
@@ -0,0 +1,54 @@
+use std::{env, io::{Cursor, Write}};
+use super::*;
+
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut w = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        w.start_file(*name, zip::write::FileOptions::default()).unwrap();
+        w.write_all(data).unwrap();
+    }
+    w.finish().unwrap().into_inner()
+}
+
+#[test]
+fn test_split() {
+    assert_eq!(split("pack.zip:games/pong.ch8"), Some(("pack.zip", "games/pong.ch8")));
+    assert_eq!(split("Pack.ZIP:pong.ch8"), Some(("Pack.ZIP", "pong.ch8")));
+    assert_eq!(split("pong.ch8"), None);
+    // lowercasing İ makes it longer
+    assert_eq!(split("İ/pack.Zip:pong.ch8"), Some(("İ/pack.Zip", "pong.ch8")));
+}
+
+#[test]
+fn test_members() {
+    let z = zip(&[("readme.txt", b"hi"), ("games/pong.ch8", &[0x12, 0x00]), ("tank.SC8", &[0x00])]);
+    assert_eq!(list_members(Cursor::new(&z)).unwrap(), vec!["games/pong.ch8", "tank.SC8"]);
+    assert_eq!(read_member(Cursor::new(&z), "games/pong.ch8").unwrap(), [0x12, 0x00]);
+    let e = read_member(Cursor::new(&z), "nope.ch8").unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn test_list() {
+    let dir = env::temp_dir().join(format!("c8rust-archive-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("b.ch8"), [0x00]).unwrap();
+    fs::write(dir.join("notes.txt"), "x").unwrap();
+    fs::write(dir.join("sub/a.xo8"), [0x00]).unwrap();
+    let z = dir.join("pack.zip");
+    fs::write(&z, zip(&[("c.ch8", &[0x12, 0x34])])).unwrap();
+    let d = dir.to_str().unwrap();
+    let zp = z.to_str().unwrap();
+
+    let roms = list(d).unwrap().unwrap();
+    assert_eq!(roms, vec![format!("{}/b.ch8", d), format!("{}:c.ch8", zp), format!("{}/sub/a.xo8", d)]);
+    assert_eq!(list(zp).unwrap().unwrap(), vec![format!("{}:c.ch8", zp)]);
+    assert_eq!(list(&roms[0]).unwrap(), None);
+    assert_eq!(read(&roms[1]).unwrap(), [0x12, 0x34]);
+
+    // a broken archive and a symlink back up don't stop the listing
+    fs::write(dir.join("broken.zip"), "not a zip").unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("sub/up")).unwrap();
+    assert_eq!(list(d).unwrap().unwrap(), roms);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+// a cartridge as Octo lays it out: the payload's nybbles in the low bits,
+// with a label colour in the high bits
+pub fn build(json: &str, width: u16) -> Vec<u8> {
+    let mut bytes = (json.len() as u32).to_be_bytes().to_vec();
+    bytes.extend(json.bytes());
+    let mut pixels: Vec<u8> = bytes.iter().flat_map(|b| vec![0x30 | b >> 4, 0x30 | b & 0xf]).collect();
+    let height = (pixels.len() as u16).div_ceil(width);
+    pixels.resize(width as usize * height as usize, 0x30);
+    let palette: Vec<u8> = (0..=255).flat_map(|i| vec![i, i, i]).collect();
+    let mut out = Vec::new();
+    {
+        let mut enc = gif::Encoder::new(&mut out, width, height, &palette).unwrap();
+        enc.write_frame(&gif::Frame::from_indexed_pixels(width, height, &pixels, None)).unwrap();
+    }
+    out
+}
+
+#[test]
+fn test_load() {
+    let json = r##"{"options": {"tickrate": 20, "fillColor": "#FFAA00", "backgroundColor": "#000000",
+                    "shiftQuirks": false, "loadStoreQuirks": true, "vBlankQuirks": true},
+                    "program": ": main\n  0x00 0xE0  # clear\n  18 0b10 "}"##;
+    let gif = build(json, 64);
+    assert!(is_cartridge(&gif));
+    assert_eq!(payload(&gif).unwrap(), json);
+
+    let (p, o) = load(&gif).unwrap();
+    assert_eq!(p, [0x00, 0xe0, 0x12, 0x02]);
+    assert_eq!(o.ipf, Some(20));
+    assert_eq!(o.quirks, vec![("memory", false), ("shifting", false)]);
+    assert_eq!((o.fg, o.bg, o.buzz), (Some((0xff, 0xaa, 0x00)), Some((0, 0, 0)), None));
+}
+
+#[test]
+fn test_octo_source() {
+    // the options as Octo saves them, and source to compile
+    let options = r##"{"tickrate": 20, "fillColor": "#FFCC00", "fillColor2": "#FF6600", "blendColor": "#662200",
+                       "backgroundColor": "#996600", "buzzColor": "#FFAA00", "quietColor": "#000000",
+                       "shiftQuirks": false, "loadStoreQuirks": false, "vfOrderQuirks": false, "clipQuirks": false,
+                       "vBlankQuirks": false, "jumpQuirks": false, "screenRotation": 0, "maxSize": 3215,
+                       "touchInputMode": "none", "logicQuirks": false, "fontStyle": "octo"}"##;
+    let json = serde_json::json!({"options": serde_json::from_str::<Value>(options).unwrap(),
+                                  "program": crate::octo::octo_test::BOUNCE}).to_string();
+    let (p, o) = load(&build(&json, 128)).unwrap();
+    assert_eq!(p, crate::octo::compile(crate::octo::octo_test::BOUNCE).unwrap());
+    assert_eq!(&p[..6], [0x12, 0x06, 0x60, 0xf0, 0xf0, 0x60]);
+    assert_eq!(o.ipf, Some(20));
+    assert_eq!(o.buzz, Some((0xff, 0xaa, 0x00)));
+
+    let gif = build(r#"{"program": ": main\n  clear\n  jump nowhere"}"#, 64);
+    let e = load(&gif).unwrap_err();
+    assert_eq!(e.to_string(), "cartridge: compiling the program: line 3: undefined label \"nowhere\"");
+}
+
+#[test]
+fn test_invalid() {
+    assert!(load(b"GIF89a nonsense").is_err());
+    assert!(load(&build("not json", 16)).is_err());
+    // cut short
+    let mut gif = build(r#"{"program": "0"}"#, 16);
+    let n = gif.len();
+    gif.truncate(n - 12);
+    assert!(load(&gif).is_err());
+}
@@ -0,0 +1,19 @@
+use super::*;
+
+#[test]
+fn test_header() {
+    let mut buf = Vec::new();
+    Cast::new(&mut buf, 80, 24).unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert!(s.starts_with("{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": "));
+    assert!(s.ends_with("}\n"));
+}
+
+#[test]
+fn test_event() {
+    let mut buf = Vec::new();
+    let mut c = Cast::new(&mut buf, 80, 24).unwrap();
+    c.event(1.5, b"\x1b[1;1H\"#\\\n").unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert_eq!(s.lines().nth(1), Some("[1.500000, \"o\", \"\\u001b[1;1H\\\"#\\\\\\n\"]"));
+}
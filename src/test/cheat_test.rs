@@ -0,0 +1,89 @@
+use std::{env, io::ErrorKind};
+use super::*;
+use crate::rom::ROM;
+
+const FILE: &str = "# c8rust cheats, by SHA-1 of the ROM
+
+[aaaa]
+freeze 2f4 03 lives
+set 300 ff
+
+[bbbb]
+freeze 000 01
+";
+
+#[test]
+fn test_search() {
+    let mut ram = vec![0u8; RAM_SIZE];
+    ram[0x10] = 3;
+    ram[0x20] = 3;
+    let mut s = Search::new(&ram);
+    s.filter(&ram, Filter::Equal(3));
+    assert_eq!(s.candidates, vec![0x10, 0x20]);
+    // one life lost
+    ram[0x10] = 2;
+    ram[0x30] = 9;
+    s.filter(&ram, Filter::Decreased);
+    assert_eq!(s.candidates, vec![0x10]);
+    assert_eq!(s.old(0x10), 2);
+    s.filter(&ram, Filter::Unchanged);
+    assert_eq!(s.candidates, vec![0x10]);
+    s.filter(&ram, Filter::Changed);
+    assert!(s.candidates.is_empty());
+}
+
+#[test]
+fn test_parse() {
+    let all = parse(FILE).unwrap();
+    assert_eq!(all["aaaa"], vec![
+        Cheat { addr: 0x2f4, value: 3, freeze: true, name: String::from("lives") },
+        Cheat { addr: 0x300, value: 0xff, freeze: false, name: String::new() },
+    ]);
+    assert_eq!(to_text(&all), FILE);
+
+    let e = parse("freeze 200 01\n").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+    let e = parse("[aaaa]\nfreeze 1000 01\n").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+}
+
+#[test]
+fn test_load_save() {
+    let path = env::temp_dir().join(format!("c8rust-cheats-{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+    fs::write(path, FILE).unwrap();
+
+    let mut ch = Cheats::load(Some(path), "aaaa").unwrap();
+    assert_eq!(ch.list.len(), 2);
+    ch.list.clear();
+    ch.add(0x2f4, 5);
+    ch.add(0x2f4, 4);
+    ch.save().unwrap();
+    // other ROMs' cheats are kept
+    let all = parse(&fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(all["aaaa"], vec![Cheat { addr: 0x2f4, value: 4, freeze: true, name: String::new() }]);
+    assert_eq!(all["bbbb"].len(), 1);
+    fs::remove_file(path).unwrap();
+
+    // a missing file is created on the first save
+    assert!(Cheats::load(Some(path), "aaaa").unwrap().list.is_empty());
+}
+
+#[test]
+fn test_apply() {
+    // v0 := 0, i := 0x300, save v0, loop
+    let r = ROM::new_prog(&[0x60, 0x00, 0xa3, 0x00, 0xf0, 0x55, 0x12, 0x06]).unwrap();
+    let mut c = Cpu::new(Some(r)).unwrap();
+    let mut ch = Cheats::load(None, "").unwrap();
+    ch.add(0x300, 7);
+    ch.add(0x301, 8);
+    ch.list[1].freeze = false;
+    ch.apply_all(&mut c);
+    assert_eq!(&c.ram()[0x300..0x302], [7, 8]);
+    c.frame(&[false; 16]).unwrap();
+    ch.apply(&mut c);
+    assert_eq!(&c.ram()[0x300..0x302], [7, 8]);
+    c.poke(0x301, 0);
+    ch.apply(&mut c);
+    assert_eq!(c.ram()[0x301], 0);
+}
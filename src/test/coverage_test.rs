@@ -0,0 +1,59 @@
+use super::*;
+use crate::{cpu::Cpu, rom::ROM};
+
+// i := 0x20c, sprite v0 v0 2, bcd v0, loop,
+// then a never executed instruction, padding and the sprite rows
+const PROG: [u8; 14] = [
+    0xa2, 0x0c, 0xd0, 0x02, 0xf0, 0x33, 0x12, 0x06,
+    0x60, 0x01, 0x00, 0x00, 0xf0, 0x90,
+];
+
+fn run(p: &[u8], format: Format) -> String {
+    let mut c = Cpu::new(Some(ROM::new_prog(p).unwrap())).unwrap();
+    c.enable_coverage();
+    c.frame(&[false; 16]).unwrap();
+    c.coverage_report(format, "prog.ch8").unwrap()
+}
+
+#[test]
+fn test_listing() {
+    let mut cv = Coverage::new();
+    let mut ram = [0; RAM_SIZE];
+    ram[PC_BASE..PC_BASE + PROG.len()].copy_from_slice(&PROG);
+    for pc in &[0x200, 0x202, 0x204, 0x206] { cv.execute(*pc); }
+    cv.read(0x20c);
+    cv.read(0x20d);
+
+    let code: Vec<(usize, bool)> = cv.listing(&ram, PROG.len()).iter().map(|l| (l.addr, l.code)).collect();
+    assert_eq!(code, vec![
+        (0x200, true), (0x202, true), (0x204, true), (0x206, true),
+        (0x208, true),                  // untouched but decodes
+        (0x20a, false), (0x20b, false), // 0000
+        (0x20c, false), (0x20d, false), // sprite rows
+    ]);
+}
+
+#[test]
+fn test_annotated() {
+    let r = run(&PROG, Format::Annotated);
+    // bcd overwrites the sprite, so its rows are both read and written
+    assert!(r.starts_with("4/5 instructions executed (80.0%), 2 bytes read, 3 bytes written\n"));
+    assert!(r.contains("          7 x-- 206  1206 jump 0x206\n"));
+    assert!(r.contains("          0 --- 208  6001 v0 := 0x01\n"));
+    assert!(r.contains("            -rw 20c  00   ........\n"));
+    assert!(r.contains("            --w 20e  00   ........\n"));
+}
+
+#[test]
+fn test_lcov() {
+    let r = run(&PROG, Format::Lcov);
+    assert!(r.starts_with("TN:\nSF:prog.ch8\nDA:512,1\n"));
+    assert!(r.contains("DA:518,7\nDA:520,0\n"));
+    assert!(r.ends_with("LF:5\nLH:4\nend_of_record\n"));
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(Format::from_name("lcov").unwrap(), Format::Lcov);
+    assert!(Format::from_name("html").is_err());
+}
@@ -11,19 +11,16 @@ fn exec_test_prog(p: &Vec<u16>, c: &mut Cpu) {
 #[test]
 fn test_new() {
     // initialize without program
-    let c: Cpu = Cpu::new(None, false).unwrap();
+    let c: Cpu = Cpu::new(None).unwrap();
     assert_eq!(c.sp, 0x0);
     assert_eq!(c.pc, 0x200);
 
     // font in interpreter memory
-    for ii in 0..FONT.len() {
-        assert_eq!(c.ram[ii], FONT[ii]);
-    }
+    assert_eq!(&c.ram[..FONT.len()], &FONT[..]);
 
     // initialize with program
     let c: Cpu = Cpu::new(
-        Some(ROM::new_prog(&[0xde, 0xad, 0xbe, 0xef]).unwrap()),
-        false
+        Some(ROM::new_prog(&[0xde, 0xad, 0xbe, 0xef]).unwrap())
     ).unwrap();
     assert_eq!(c.sp, 0x0);
     assert_eq!(c.ram[0x200], 0xde);
@@ -32,15 +29,13 @@ fn test_new() {
     assert_eq!(c.ram[0x203], 0xef);
 
     // font in interpreter memory
-    for ii in 0..FONT.len() {
-        assert_eq!(c.ram[ii], FONT[ii]);
-    }
+    assert_eq!(&c.ram[..FONT.len()], &FONT[..]);
 }
 
 #[test]
 fn test_cpu_reset() {
     // reset on no program
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     c.ram[0x200] = 0x42;
     c.pc = 0xdead;
     c.i = 0xbeef;
@@ -51,8 +46,7 @@ fn test_cpu_reset() {
 
     // reset with some program
     let mut c: Cpu = Cpu::new(
-        Some(ROM::new_prog(&[0xde, 0xad, 0xbe, 0xef]).unwrap()),
-        false
+        Some(ROM::new_prog(&[0xde, 0xad, 0xbe, 0xef]).unwrap())
     ).unwrap();
     c.pc = 0x204;
     c.reset();
@@ -64,10 +58,45 @@ fn test_cpu_reset() {
     assert_eq!(c.i, 0x00);
 }
 
+#[test]
+fn test_snapshot() {
+    // v0 := random, store at 0x300, loop
+    let prog = [0xa3, 0x00, 0xc0, 0xff, 0xf0, 0x55, 0x12, 0x02];
+    let mut c = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    let idle = [false; 16];
+    c.frame(&idle).unwrap();
+    let st = c.snapshot();
+    c.frame(&idle).unwrap();
+    let (ram, v, frames) = (c.ram, c.v, c.frames);
+
+    // loading replays the same random numbers
+    c.frame(&idle).unwrap();
+    c.restore(&st);
+    assert_eq!(c.frames, 1);
+    c.frame(&idle).unwrap();
+    assert_eq!((c.ram, c.v, c.frames), (ram, v, frames));
+}
+
+#[test]
+fn test_state() {
+    let mut c = Cpu::new(Some(ROM::new_prog(&[0x60, 0x2a, 0xa3, 0x00]).unwrap())).unwrap();
+    c.set_ipf(2);
+    c.frame(&[false; 16]).unwrap();
+    let st = c.state();
+    assert!(st.starts_with("pc  204  0000  "), "{}", st);
+    assert!(st.contains("\nv   2a 00 "));
+    assert!(st.contains("\ni   300  sp 0  stack []\n"));
+    assert!(st.ends_with("cycles 2  frames 1\n"));
+
+    // a PC run off the end of RAM
+    c.pc = RAM_SIZE - 1;
+    assert!(c.state().starts_with("pc  fff  outside RAM\n"));
+}
+
 /* Opcodes {{{ */
 #[test]
 fn test_0x00e0() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     c.vram[3][4] = 1;
     c.vram[63][31] = 2;
     exec_test_prog(&vec![0x00e0], &mut c);
@@ -80,7 +109,7 @@ fn test_0x00e0() {
 
 #[test]
 fn test_0x00ee() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     c.s[0] = 0x200;
     exec_test_prog(&vec![0x00ee], &mut c);
     assert_eq!(c.sp, 0x0); // return address poped from stack
@@ -89,7 +118,7 @@ fn test_0x00ee() {
 
 #[test]
 fn test_0x2nnn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x2242], &mut c);
     assert_eq!(c.sp, 0x1); // return address on stack
     assert_eq!(c.s[c.sp], 0x202); // return address after call
@@ -98,37 +127,37 @@ fn test_0x2nnn() {
 
 #[test]
 fn test_0x1nnn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x1242], &mut c);
     assert_eq!(c.pc, 0x242); // pc = nnn
 }
 
 #[test]
 fn test_0x3xnn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6022, 0x3021], &mut c);
     assert_eq!(c.pc, 0x204); // v[x] != nn, pc + 1*OP_LEN
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0x3021], &mut c);
     assert_eq!(c.pc, 0x206); // v[x] == nn, pc + 2*OP_LEN
 }
 
 #[test]
 fn test_0x4xnn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6022, 0x4022], &mut c);
     assert_eq!(c.pc, 0x204); // v[x] == nn, pc + 1*OP_LEN
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6022, 0x4021], &mut c);
     assert_eq!(c.pc, 0x206); // v[x] != nn, pc + 2*OP_LEN
 }
 
 #[test]
 fn test_0x5xy0() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6022, 0x6123, 0x5010], &mut c);
     assert_eq!(c.pc, 0x206); // v[x] != v[y], pc + 1*OP_LEN
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6022, 0x6122, 0x5010], &mut c);
     assert_eq!(c.pc, 0x208); // v[x] == v[y], pc + 2*OP_LEN
 }
@@ -137,7 +166,7 @@ fn test_0x5xy0() {
 fn test_0x6xnn() {
     // 0x6xnn => vx := nn
     // v[0] = 0x42
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042], &mut c);
     assert_eq!(c.pc, 0x202);
     assert_eq!(c.v[0], 0x42);
@@ -147,7 +176,7 @@ fn test_0x6xnn() {
 fn test_0x7xnn() {
     // 0x7xnn => vx += nn
     // v[0] += 1
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x7001], &mut c);
     assert_eq!(c.pc, 0x202);
     assert_eq!(c.v[0], 1);
@@ -155,7 +184,7 @@ fn test_0x7xnn() {
 
 #[test]
 fn test_0x8xn0() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042, 0x8100], &mut c);
     assert_eq!(c.pc, 0x204);
     assert_eq!(c.v[0], 0x42);
@@ -164,7 +193,7 @@ fn test_0x8xn0() {
 
 #[test]
 fn test_0x8xy1() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042, 0x61ff, 0x8011], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0xff);
@@ -173,7 +202,7 @@ fn test_0x8xy1() {
 
 #[test]
 fn test_0x8xy2() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042, 0x61ff, 0x8012], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0xff);
@@ -182,7 +211,7 @@ fn test_0x8xy2() {
 
 #[test]
 fn test_0x8xy3() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042, 0x61ff, 0x8013], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0xff);
@@ -191,7 +220,7 @@ fn test_0x8xy3() {
 
 #[test]
 fn test_0x8xy4() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0x6121, 0x8014], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0x21);
@@ -200,7 +229,7 @@ fn test_0x8xy4() {
 
 #[test]
 fn test_0x8xy5() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6063, 0x6121, 0x8015], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0x21);
@@ -209,7 +238,7 @@ fn test_0x8xy5() {
 
 #[test]
 fn test_0x8xy6() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6084, 0x6101, 0x8016], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0x01);
@@ -218,7 +247,7 @@ fn test_0x8xy6() {
 
 #[test]
 fn test_0x8xy7() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6020, 0x6162, 0x8017], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0x62);
@@ -227,7 +256,7 @@ fn test_0x8xy7() {
 
 #[test]
 fn test_0x8xye() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0x6101, 0x801e], &mut c);
     assert_eq!(c.pc, 0x206);
     assert_eq!(c.v[1], 0x01);
@@ -236,17 +265,17 @@ fn test_0x8xye() {
 
 #[test]
 fn test_0x9xy0() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0x6121, 0x9010], &mut c);
     assert_eq!(c.pc, 0x206); // v[x] == v[y], pc + 1*OP_LEN
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0x6122, 0x9010], &mut c);
     assert_eq!(c.pc, 0x208); // v[x] != v[y], pc + 2*OP_LEN
 }
 
 #[test]
 fn test_0xannn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0xa042], &mut c);
     assert_eq!(c.pc, 0x202);
     assert_eq!(c.i, 0x042); // i = nnn
@@ -254,15 +283,35 @@ fn test_0xannn() {
 
 #[test]
 fn test_0xbnnn() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6021, 0xb221], &mut c);
     assert_eq!(c.pc, 0x242); // pc = v[0] + nnn
 }
 
+#[test]
+fn test_0xcxnn() {
+    // the default generator is deterministic
+    let mut a = Cpu::new(None).unwrap();
+    let mut b = Cpu::new(None).unwrap();
+    let prog = vec![0xc0ff, 0xc1ff, 0xc20f, 0xc300];
+    exec_test_prog(&prog, &mut a);
+    exec_test_prog(&prog, &mut b);
+    assert_eq!(a.v, b.v);
+    assert_eq!(a.pc, 0x208);
+    assert!(a.v[2] <= 0x0f); // masked by nn
+    assert_eq!(a.v[3], 0x00);
+
+    // reseeding restarts the sequence
+    a.reseed(DEFAULT_SEED);
+    a.pc = PC_BASE;
+    exec_test_prog(&prog, &mut a);
+    assert_eq!(a.v, b.v);
+}
+
 #[test]
 fn test_0xdxyn() {
     // sprite test: use FONT[0] = '0'
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     c.v[0] = 0;
     c.v[1] = 0;
     c.i = 0x000;
@@ -273,15 +322,116 @@ fn test_0xdxyn() {
     assert_eq!(&s[1][0..5], [1, 0, 0, 0, 1]);
     assert_eq!(&s[2][0..5], [1, 0, 0, 0, 1]);
     assert_eq!(&s[3][0..5], [1, 1, 1, 1, 1]);
+    assert_eq!(c.last_sprite(), (0x000, &FONT[..5]));
 }
 
 #[test]
 fn test_0xfx1e() {
-    let mut c = Cpu::new(None, false).unwrap();
+    let mut c = Cpu::new(None).unwrap();
     exec_test_prog(&vec![0x6042, 0xf01e], &mut c);
     assert_eq!(c.pc, 0x204);
     assert_eq!(c.i, 0x0042); // i += v[x]
 }
+
+#[test]
+fn test_0xex9e() {
+    // only the low nybble of v[x] picks the key
+    let pc = |vx: u8, op: u16| {
+        let mut c = Cpu::new(None).unwrap();
+        c.keyb[0x3] = true;
+        c.v[0] = vx;
+        exec_test_prog(&vec![op], &mut c);
+        c.pc
+    };
+    assert_eq!(pc(0x13, 0xe09e), pc(0x03, 0xe09e));
+    assert_eq!(pc(0x13, 0xe0a1), pc(0x03, 0xe0a1));
+    assert_ne!(pc(0x13, 0xe09e), pc(0x13, 0xe0a1));
+}
 /* }}} */
 
+#[test]
+fn test_bounds() {
+    // running past the end of RAM or the stack is an error, not a panic
+    let run = |f: &dyn Fn(&mut Cpu), op: u16| {
+        let mut c = Cpu::new(None).unwrap();
+        f(&mut c);
+        c.opcode_init(op);
+        c.icycle().unwrap_err().to_string()
+    };
+    assert!(run(&|c| c.i = 0xffe, 0xd005).contains("runs past the end of RAM"));
+    assert!(run(&|c| c.i = 0xffe, 0xf033).contains("runs past the end of RAM"));
+    assert!(run(&|c| c.i = 0xff8, 0xff55).contains("runs past the end of RAM"));
+    assert!(run(&|c| c.i = 0xff8, 0xff65).contains("runs past the end of RAM"));
+    assert!(run(&|c| c.sp = STACK_SIZE - 1, 0x2200).contains("stack overflow"));
+
+    let mut c = Cpu::new(None).unwrap();
+    c.pc = 0xfff;
+    assert!(c.icycle().unwrap_err().to_string().contains("past the end of RAM"));
+    // right up to the end is fine
+    let mut c = Cpu::new(None).unwrap();
+    c.i = 0xffb;
+    exec_test_prog(&vec![0xd005, 0xf033, 0xf465], &mut c);
+}
+
+#[test]
+fn test_breakpoint() {
+    // v0 += 1, loop
+    let mut c = Cpu::new(Some(ROM::new_prog(&[0x70, 0x01, 0x12, 0x00]).unwrap())).unwrap();
+    let idle = [false; 16];
+    assert!(c.toggle_breakpoint(0x202));
+    c.frame(&idle).unwrap();
+    assert_eq!((c.at_breakpoint(), c.pc, c.v[0], c.fcycle), (Some(0x202), 0x202, 1, 1));
+    // resuming executes the instruction, stopping again the next time round
+    c.frame(&idle).unwrap();
+    assert_eq!((c.at_breakpoint(), c.v[0], c.fcycle), (Some(0x202), 2, 3));
+    // stepping counts towards the frame
+    c.step(&idle).unwrap();
+    c.step(&idle).unwrap();
+    assert_eq!((c.at_breakpoint(), c.v[0], c.fcycle), (Some(0x202), 3, 5));
+    assert!(!c.toggle_breakpoint(0x202));
+    c.frame(&idle).unwrap();
+    assert_eq!((c.at_breakpoint(), c.frames, c.fcycle), (None, 1, 0));
+    assert_eq!(c.v[0], 5);
+}
+
 /* vim: set fdm=marker : */
+
+#[test]
+fn test_quirks() {
+    use crate::quirks::{Platform, Quirks};
+    let chip8 = Quirks::platform(Platform::Chip8);
+    let schip = Quirks::platform(Platform::Schip);
+
+    // vF reset by logic ops
+    let mut c = Cpu::new(None).unwrap();
+    c.set_quirks(chip8);
+    exec_test_prog(&vec![0x6f01, 0x6003, 0x8011], &mut c);
+    assert_eq!(c.v[0xf], 0);
+
+    // shift vy into vx
+    let mut c = Cpu::new(None).unwrap();
+    c.set_quirks(chip8);
+    exec_test_prog(&vec![0x60ff, 0x6103, 0x8016], &mut c);
+    assert_eq!((c.v[0], c.v[0xf]), (0x01, 1));
+
+    // I left after the registers
+    let mut c = Cpu::new(None).unwrap();
+    c.set_quirks(chip8);
+    exec_test_prog(&vec![0xa300, 0xf255], &mut c);
+    assert_eq!(c.i, 0x303);
+
+    // bxnn jumps by vx
+    let mut c = Cpu::new(None).unwrap();
+    c.set_quirks(schip);
+    exec_test_prog(&vec![0x6010, 0x6220, 0xb210], &mut c);
+    assert_eq!(c.pc, 0x230);
+
+    // clipped at the right edge, wrapped by default
+    for (q, wrapped) in &[(schip, 0), (Quirks::default(), 1)] {
+        let mut c = Cpu::new(None).unwrap();
+        c.set_quirks(*q);
+        exec_test_prog(&vec![0x603e, 0x6100, 0xa000, 0xd015], &mut c);
+        assert_eq!(c.vram[63][0], 1);
+        assert_eq!(c.vram[0][0], *wrapped);
+    }
+}
@@ -0,0 +1,270 @@
+use tui::{backend::TestBackend, Terminal};
+
+use super::*;
+use crate::{cpu::FONT, movie::Movie, rom::ROM};
+
+fn cpu() -> Cpu {
+    // i := 0x300, bcd v0, loop
+    Cpu::new(Some(ROM::new_prog(&[0xa3, 0x00, 0xf0, 0x33, 0x12, 0x04]).unwrap())).unwrap()
+}
+
+#[test]
+fn test_passthrough() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    // closed: only the toggles are taken
+    assert!(!d.key(Key::Down, &mut c).unwrap());
+    assert!(!d.key(Key::Char('a'), &mut c).unwrap());
+    assert!(d.key(Key::F(1), &mut c).unwrap());
+    assert!(d.open);
+    // open and running: navigation is taken, keypad keys are not
+    assert!(d.key(Key::Down, &mut c).unwrap());
+    assert!(!d.key(Key::Char('a'), &mut c).unwrap());
+    assert!(!d.key(Key::Ctrl('c'), &mut c).unwrap());
+}
+
+#[test]
+fn test_navigate() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    d.key(Key::F(1), &mut c).unwrap();
+    d.key(Key::Char('\t'), &mut c).unwrap();
+    assert_eq!(d.pane, Pane::Memory);
+    d.key(Key::Down, &mut c).unwrap();
+    d.key(Key::Right, &mut c).unwrap();
+    assert_eq!(d.mem.cursor, 0x211);
+    d.key(Key::PageUp, &mut c).unwrap();
+    assert_eq!(d.mem.cursor, 0x111);
+    // wraps around the ends of RAM
+    d.mem.goto(0);
+    d.key(Key::Left, &mut c).unwrap();
+    assert_eq!(d.mem.cursor, RAM_SIZE - 1);
+
+    c.frame(&[false; 16]).unwrap();
+    d.key(Key::Char('i'), &mut c).unwrap();
+    assert_eq!(d.mem.cursor, 0x300);
+    d.key(Key::Char('g'), &mut c).unwrap();
+    assert_eq!(d.mem.cursor, 0x204);
+}
+
+#[test]
+fn test_edit() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    d.key(Key::F(1), &mut c).unwrap();
+    d.key(Key::Char('\t'), &mut c).unwrap();
+    d.key(Key::Char('p'), &mut c).unwrap();
+    assert!(d.paused);
+    d.mem.goto(0x300);
+    d.key(Key::Char('b'), &mut c).unwrap();
+    assert_eq!(d.mem.nibble, Some(0xb));
+    assert_eq!(c.ram()[0x300], 0);
+    d.key(Key::Char('E'), &mut c).unwrap();
+    assert_eq!(c.ram()[0x300], 0xbe);
+    assert_eq!(d.mem.cursor, 0x301);
+    // escape drops a half typed byte
+    d.key(Key::Char('1'), &mut c).unwrap();
+    d.key(Key::Esc, &mut c).unwrap();
+    d.key(Key::Char('2'), &mut c).unwrap();
+    assert_eq!(d.mem.nibble, Some(0x2));
+    assert_eq!(c.ram()[0x301], 0);
+}
+
+#[test]
+fn test_written() {
+    let mut c = cpu();
+    assert_eq!(c.written_ago(0x300), None);
+    c.frame(&[false; 16]).unwrap();
+    assert_eq!(c.written_ago(0x300), Some(1));
+    c.frame(&[false; 16]).unwrap();
+    assert_eq!(c.written_ago(0x302), Some(2));
+}
+
+#[test]
+fn test_disasm() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    d.key(Key::F(1), &mut c).unwrap();
+    // breakpoint on the selected line, or on PC when following it
+    d.key(Key::Down, &mut c).unwrap();
+    d.key(Key::Char('b'), &mut c).unwrap();
+    assert!(c.breakpoints().contains(&0x202));
+    d.key(Key::Char('g'), &mut c).unwrap();
+    d.key(Key::Char('\n'), &mut c).unwrap();
+    assert!(c.breakpoints().contains(&0x200));
+
+    // stepping only while paused
+    assert!(!d.key(Key::Char('n'), &mut c).unwrap());
+    d.key(Key::Char('p'), &mut c).unwrap();
+    d.key(Key::Char('n'), &mut c).unwrap();
+    assert_eq!(c.pc(), 0x202);
+
+    let text: Vec<String> = d.dis.listing(&c, &[0x200, 0x202, 0x204])
+        .iter()
+        .map(|l| l.0.iter().map(|s| s.content.as_ref()).collect())
+        .collect();
+    assert_eq!(text, vec![
+        "●   200  a300  i := 0x300",
+        "●▶  202  f033  bcd v0",
+        "  » 204  1204  jump 0x204",
+    ]);
+}
+
+#[test]
+fn test_sprites() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    d.key(Key::F(1), &mut c).unwrap();
+    d.key(Key::Char('\t'), &mut c).unwrap();
+    d.key(Key::Char('\t'), &mut c).unwrap();
+    assert_eq!(d.pane, Pane::Sprites);
+
+    // follows I until moved
+    c.frame(&[false; 16]).unwrap();
+    assert_eq!(d.spr.base(&c), 0x300);
+    d.key(Key::Down, &mut c).unwrap();
+    assert_eq!(d.spr.source, Source::Addr(0x305));
+    d.key(Key::Char('-'), &mut c).unwrap();
+    d.key(Key::Up, &mut c).unwrap();
+    assert_eq!(d.spr.source, Source::Addr(0x301));
+    d.key(Key::Char('t'), &mut c).unwrap();
+    assert_eq!((d.spr.source, d.spr.height), (Source::Addr(0), 5));
+    d.key(Key::Char('l'), &mut c).unwrap();
+    assert_eq!(d.spr.source, Source::Last);
+}
+
+#[test]
+fn test_half_blocks() {
+    // the font's '0'
+    assert_eq!(half_blocks(&FONT[..5]), vec!["█▀▀█    ", "█  █    ", "▀▀▀▀    "]);
+}
+
+#[test]
+fn test_cheat_search() {
+    let mut c = cpu();
+    let mut d = Debugger::new();
+    d.key(Key::F(1), &mut c).unwrap();
+    for _ in 0..3 { d.key(Key::Char('\t'), &mut c).unwrap(); }
+    assert_eq!(d.pane, Pane::Cheats);
+
+    // bcd v0 of 0 at 0x300..0x303, then 0x302 goes up
+    c.frame(&[false; 16]).unwrap();
+    d.key(Key::Char('n'), &mut c).unwrap();
+    c.poke(0x302, 9);
+    d.key(Key::Char('>'), &mut c).unwrap();
+    assert_eq!(d.cht.listed(), [0x302]);
+    d.key(Key::Char('#'), &mut c).unwrap();
+    d.key(Key::Char('0'), &mut c).unwrap();
+    d.key(Key::Char('9'), &mut c).unwrap();
+    assert_eq!(d.cht.listed(), [0x302]);
+
+    // freeze it, then set it to another value
+    d.key(Key::Char('\n'), &mut c).unwrap();
+    assert_eq!(d.cht.cheats.list[0].value, 9);
+    d.key(Key::Down, &mut c).unwrap();
+    d.key(Key::Char(':'), &mut c).unwrap();
+    d.key(Key::Char('4'), &mut c).unwrap();
+    d.key(Key::Char('2'), &mut c).unwrap();
+    assert_eq!(c.ram()[0x302], 0x42);
+    c.poke(0x302, 0);
+    d.frame(&mut c, &[false; 16]).unwrap();
+    assert_eq!(c.ram()[0x302], 0x42);
+
+    d.key(Key::Delete, &mut c).unwrap();
+    assert!(d.cht.cheats.list.is_empty());
+}
+
+#[test]
+fn test_locked_replay() {
+    // v1 counts the loops key 1 is held for, v2 all of them
+    let prog = [0x60, 0x01, 0xe0, 0x9e, 0x12, 0x08, 0x71, 0x01, 0x72, 0x01, 0x12, 0x02];
+    let mut c = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    let mut d = Debugger::new().locked(true);
+    let mut m = Movie::new(0, "std");
+    let mut key = [false; 16];
+    key[1] = true;
+    let mut record = |c: &mut Cpu, key: &[bool; 16]| {
+        c.frame(key).unwrap();
+        m.push(key);
+    };
+    record(&mut c, &key);
+
+    // stepping and editing memory while paused are refused, but still taken from the keypad
+    d.key(Key::F(1), &mut c).unwrap();
+    d.key(Key::Char('p'), &mut c).unwrap();
+    let before = c.state();
+    assert!(d.key(Key::Char('n'), &mut c).unwrap());
+    assert_eq!(d.message.as_deref(), Some("not while recording a movie"));
+    d.key(Key::Char('\t'), &mut c).unwrap();
+    d.mem.goto(0x204);
+    d.key(Key::Char('0'), &mut c).unwrap();
+    d.key(Key::Char('0'), &mut c).unwrap();
+    assert_eq!(c.state(), before);
+    assert_eq!(c.ram()[0x204], 0x12);
+    for _ in 0..2 { d.key(Key::Char('\t'), &mut c).unwrap(); }
+    assert_eq!(d.pane, Pane::Cheats);
+    assert!(d.key(Key::Char(':'), &mut c).unwrap());
+    assert!(d.cht.entry.is_none());
+
+    d.key(Key::Char('p'), &mut c).unwrap();
+    record(&mut c, &[false; 16]);
+    record(&mut c, &key);
+
+    // the movie replays to the same machine
+    let mut r = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    for k in m.frames() { r.frame(&k).unwrap(); }
+    assert_eq!(r.state(), c.state());
+    assert_eq!(r.ram(), c.ram());
+}
+
+#[test]
+fn test_memory_resize() {
+    let c = cpu();
+    let mut m = Memory::new();
+    m.goto(0xff0);
+    let mut draw = |w, h| {
+        let mut t = Terminal::new(TestBackend::new(w, h)).unwrap();
+        t.draw(|f| m.draw(f, f.size(), &c, false)).unwrap();
+        (m.top, m.per)
+    };
+    // scrolled to the end of RAM, then shown taller
+    assert_eq!(draw(80, 12), (RAM_SIZE - 10 * 16, 16));
+    assert_eq!(draw(80, 40), (RAM_SIZE - 38 * 16, 16));
+    // the cursor one row from the end at 8 bytes a row, then 16
+    assert_eq!(draw(60, 40), (RAM_SIZE - 39 * 8, 8));
+    assert_eq!(draw(80, 40), (RAM_SIZE - 38 * 16, 16));
+}
+
+#[test]
+fn test_breakpoint_replay() {
+    // v1 counts the loops key 1 is held for, v2 all of them
+    let prog = [0x60, 0x01, 0xe0, 0x9e, 0x12, 0x08, 0x71, 0x01, 0x72, 0x01, 0x12, 0x02];
+    let mut c = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    let mut d = Debugger::new().locked(true);
+    let mut m = Movie::new(0, "std");
+    let mut key = [false; 16];
+    key[1] = true;
+    let mut record = |c: &mut Cpu, d: &mut Debugger, key: &[bool; 16]| {
+        let done = d.frame(c, key).unwrap();
+        if let Some(k) = done { m.push(&k); }
+        done.is_some()
+    };
+
+    // stopped part way through a frame, which finishes with key 1 held
+    c.toggle_breakpoint(0x208);
+    assert!(!record(&mut c, &mut d, &key));
+    assert!(d.paused && c.mid_frame());
+    let mut stops = 0;
+    while !record(&mut c, &mut d, &[false; 16]) { stops += 1; }
+    assert!(stops > 0);
+    assert!(!c.mid_frame());
+    c.toggle_breakpoint(0x208);
+    assert!(record(&mut c, &mut d, &[false; 16]));
+
+    // the movie holds the keys the frames ran with, and replays to the same machine
+    let frames: Vec<[bool; 16]> = m.frames().collect();
+    assert_eq!(frames, vec![key, [false; 16]]);
+    let mut r = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    for k in m.frames() { r.frame(&k).unwrap(); }
+    assert_eq!(r.state(), c.state());
+}
@@ -0,0 +1,78 @@
+use std::io::ErrorKind;
+use super::*;
+use crate::rom::ROM;
+
+// draws the '0' glyph at (0, 0), then loops
+const PROG: [u8; 10] = [0xa0, 0x00, 0x60, 0x00, 0x61, 0x00, 0xd0, 0x15, 0x12, 0x08];
+
+fn run_prog(p: &[u8], frames: u64) -> Cpu {
+    let mut c = Cpu::new(Some(ROM::new_prog(p).unwrap())).unwrap();
+    run(&mut c, frames, None).unwrap();
+    c
+}
+
+#[test]
+fn test_pbm_roundtrip() {
+    let c = run_prog(&PROG, 1);
+    let img = pbm_write(&c.vram);
+    assert!(img.starts_with("P1\n64 32\n1 1 1 1 0 0"));
+    assert_eq!(pbm_read(img.as_bytes()).unwrap(), c.vram);
+}
+
+#[test]
+fn test_pbm_raw() {
+    let mut data = b"P4\n# comment\n64 32\n".to_vec();
+    let mut raw = vec![0u8; 8 * 32];
+    raw[0] = 0b1000_0001; // (0, 0) and (7, 0)
+    raw[8 * 31 + 7] = 0x01; // (63, 31)
+    data.extend(raw);
+    let v = pbm_read(&data).unwrap();
+    assert_eq!(v[0][0], 1);
+    assert_eq!(v[7][0], 1);
+    assert_eq!(v[63][31], 1);
+    assert_eq!(v[1][0], 0);
+}
+
+#[test]
+fn test_pbm_error() {
+    let e = pbm_read(b"P1\n8 8\n0").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+    let e = pbm_read(b"P1\n64 32\n0 1").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+}
+
+#[test]
+fn test_expect() {
+    let c = run_prog(&PROG, 1);
+    let h = hash(&c.vram);
+    assert!(Expect::parse(&format!("{:016x}", h)).unwrap().matches(&c.vram));
+    assert!(!Expect::Hash(h ^ 1).matches(&c.vram));
+    assert!(Expect::Image(Box::new(c.vram)).matches(&c.vram));
+    assert!(!Expect::Image(Box::new([[0; V_HEIGHT]; V_WIDTH])).matches(&c.vram));
+}
+
+#[test]
+fn test_expect_parse() {
+    let h = Expect::parse("0x00000000000000ff").map(|e| matches!(e, Expect::Hash(0xff)));
+    assert!(h.unwrap());
+    // anything but 16 hex digits is a file, so a typo is reported as missing
+    for s in &["ff", "golden.pmb", "0123456789abcdef0"] {
+        let e = Expect::parse(s).map(|_| ()).map_err(|e| e.kind());
+        assert_eq!(e, Err(ErrorKind::NotFound), "{}", s);
+    }
+}
+
+#[test]
+fn test_input() {
+    // wait for a key, then draw its glyph
+    let p = [0xf0, 0x0a, 0xf0, 0x29, 0x61, 0x00, 0xd1, 0x15, 0x12, 0x08];
+    let mut m = Movie::new(0, "std");
+    let mut k = [false; 16]; k[0x1] = true;
+    m.push(&[false; 16]);
+    m.push(&k);
+    let mut c = Cpu::new(Some(ROM::new_prog(&p).unwrap())).unwrap();
+    run(&mut c, 4, Some(&m)).unwrap();
+    assert_eq!(c.clock(), 4.0 / 60.0);
+    // '1' glyph, column 2 is solid
+    assert_eq!(&c.vram[2][0..5], [1, 1, 1, 1, 1]);
+}
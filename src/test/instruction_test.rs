@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn test_decode() {
+    assert_eq!(Instruction::decode(0x00e0), Some(Instruction::Clear));
+    assert_eq!(Instruction::decode(0x2242), Some(Instruction::Call(0x242)));
+    assert_eq!(Instruction::decode(0x8ab4), Some(Instruction::AddReg(0xa, 0xb)));
+    assert_eq!(Instruction::decode(0xd125), Some(Instruction::Draw(1, 2, 5)));
+    assert_eq!(Instruction::decode(0xf365), Some(Instruction::Restore(3)));
+
+    // 5xyn runs as 5xy0 whatever n is
+    assert_eq!(Instruction::decode(0x5121), Some(Instruction::SkipEqReg(1, 2)));
+
+    // not an instruction
+    assert_eq!(Instruction::decode(0x0000), None);
+    assert_eq!(Instruction::decode(0xffff), None);
+}
+
+#[test]
+fn test_display() {
+    let s = |op| Instruction::decode(op).unwrap().to_string();
+    assert_eq!(s(0x00ee), "return");
+    assert_eq!(s(0x1242), "jump 0x242");
+    assert_eq!(s(0x3a21), "if va != 0x21 then");
+    assert_eq!(s(0x6f00), "vf := 0x00");
+    assert_eq!(s(0x8017), "v0 =- v1");
+    assert_eq!(s(0xc10f), "v1 := random 0x0f");
+    assert_eq!(s(0xd01f), "sprite v0 v1 15");
+    assert_eq!(s(0xf229), "i := hex v2");
+}
+
+#[test]
+fn test_kind() {
+    for op in 0..=0xffffu16 {
+        if let Some(i) = Instruction::decode(op) {
+            // every kind is a pattern that matches its own opcode
+            let k = i.kind();
+            let hex = format!("{:04x}", op);
+            assert!(k.chars().zip(hex.chars()).all(|(p, c)| !p.is_ascii_hexdigit() || p == c),
+                    "{} doesn't match {}", k, hex);
+        }
+    }
+}
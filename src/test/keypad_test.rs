@@ -28,3 +28,15 @@ fn test_poll_error() {
     let e = poll(Some(Key::Ctrl('c'))).map_err(|e| e.kind());
     assert_eq!(e, Err(ErrorKind::Interrupted));
 }
+
+#[test]
+fn test_poll_mapped() {
+    let map: Keymap = vec![('j', 0x4), ('w', 0x2)].into_iter().collect();
+    let mut r = [false; 16]; r[0x4] = true;
+    assert_eq!(poll_mapped(Some(Key::Char('j')), &map).unwrap(), r);
+    // remapped over the usual layout, which is otherwise kept
+    let mut r = [false; 16]; r[0x2] = true;
+    assert_eq!(poll_mapped(Some(Key::Char('w')), &map).unwrap(), r);
+    let mut r = [false; 16]; r[0x0] = true;
+    assert_eq!(poll_mapped(Some(Key::Char('1')), &map).unwrap(), r);
+}
@@ -0,0 +1,98 @@
+use std::{env, thread, time::Duration};
+use super::*;
+
+fn temp(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("c8rust-launcher-{}-{}", name, std::process::id()))
+}
+
+// wait for a preview to finish running
+fn preview(l: &mut Launcher, rom: &str) -> Result<Box<Vram>, String> {
+    loop {
+        if let Some(res) = l.info(rom).preview.poll() { return res.clone(); }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_state() {
+    let path = temp("state");
+    let mut st = State::load(Some(path.clone())).unwrap();
+    assert_eq!((st.favourites.len(), st.recent.len()), (0, 0));
+    st.toggle_favourite("a.ch8");
+    st.toggle_favourite("b.ch8");
+    st.toggle_favourite("a.ch8");
+    for n in 0..RECENT + 5 { st.played(&format!("{}.ch8", n)); }
+    st.played("3.ch8");
+    st.save().unwrap();
+
+    let st = State::load(Some(path.clone())).unwrap();
+    assert_eq!(st.favourites, vec!["b.ch8"]);
+    assert_eq!(st.recent.len(), RECENT);
+    assert_eq!(&st.recent[..2], ["3.ch8", "24.ch8"]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_preview_lines() {
+    let mut vram = [[0; V_HEIGHT]; V_WIDTH];
+    vram[0][0] = 1;
+    vram[1][1] = 1;
+    vram[2][0] = 1;
+    vram[2][1] = 1;
+    let lines = preview_lines(&vram);
+    assert_eq!(lines.len(), V_HEIGHT / 2);
+    assert!(lines[0].starts_with("▀▄█ "));
+}
+
+#[test]
+fn test_browse() {
+    let dir = temp("roms");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("flags.ch8"), include_bytes!("../../roms/test/flags.ch8")).unwrap();
+    fs::write(dir.join("zero.ch8"), [0x00, 0x00]).unwrap();
+    // calls itself until the stack runs out
+    fs::write(dir.join("recurse.ch8"), [0x22, 0x00]).unwrap();
+    let state = temp("browse-state");
+    let mut l = Launcher::new(dir.to_str().unwrap(), RomDb::load(None).unwrap(), Some(state.clone())).unwrap();
+    assert_eq!(l.roms.iter().map(|r| l.name(r)).collect::<Vec<_>>(), vec!["flags.ch8", "recurse.ch8", "zero.ch8"]);
+
+    // metadata from the database, and a preview of the ROM running
+    let flags = l.roms[0].clone();
+    let info = l.info(&flags);
+    assert_eq!(info.title.as_deref(), Some("Flags test"));
+    assert!(info.details.contains(&String::from("platform  chip8")));
+    assert!(preview(&mut l, &flags).unwrap().iter().flatten().any(|p| *p == 1));
+    let (recurse, zero) = (l.roms[1].clone(), l.roms[2].clone());
+    assert_eq!(preview(&mut l, &recurse).unwrap_err(), "stack overflow!");
+    // 0000 isn't an instruction
+    assert!(preview(&mut l, &zero).is_err());
+    // drawing the list doesn't run previews, only the selected ROM's
+    l.info.remove(&zero);
+    let mut term = tui::Terminal::new(tui::backend::TestBackend::new(120, 30)).unwrap();
+    term.draw(|f| l.draw(f, f.size())).unwrap();
+    assert!(matches!(l.info(&zero).preview, Preview::Ready(..)));
+
+    l.key(Key::Down).unwrap();
+    l.key(Key::Down).unwrap();
+    l.key(Key::Down).unwrap();
+    assert_eq!(l.sel, 2);
+    l.key(Key::Char('f')).unwrap();
+    l.key(Key::Char('\t')).unwrap();
+    assert_eq!((l.tab, l.list().len()), (Tab::Favourites, 1));
+    assert_eq!(l.key(Key::Char('\n')).unwrap(), Action::Launch(l.roms[2].clone()));
+    l.key(Key::Char('\t')).unwrap();
+    assert_eq!(l.list(), &l.roms[2..]);
+    // unfavouriting from the favourites list
+    l.key(Key::Char('\t')).unwrap();
+    l.key(Key::Char('\t')).unwrap();
+    l.key(Key::Char('f')).unwrap();
+    assert_eq!((l.list().len(), l.sel), (0, 0));
+    assert_eq!(l.key(Key::Char('\n')).unwrap(), Action::Stay);
+    assert_eq!(l.key(Key::Char('q')).unwrap(), Action::Quit);
+
+    // remembered for next time
+    let st = State::load(Some(state.clone())).unwrap();
+    assert_eq!((st.favourites.len(), st.recent.len()), (0, 1));
+    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_file(&state).unwrap();
+}
@@ -0,0 +1,78 @@
+use std::io::ErrorKind;
+
+use termion::event::Key;
+
+use crate::{
+    cpu::Cpu,
+    debugger::Debugger,
+    menu::Menu,
+    rom::ROM,
+};
+
+fn cpu() -> Cpu {
+    // count up in v0 forever
+    let mut c = Cpu::new(Some(ROM::new_prog(&[0x70, 0x01, 0x12, 0x00]).unwrap())).unwrap();
+    c.set_ipf(2);
+    c
+}
+
+fn press(m: &mut Menu, c: &mut Cpu, d: &mut Debugger, keys: &[Key]) {
+    for k in keys { assert!(m.key(*k, c, d).unwrap()); }
+}
+
+#[test]
+fn test_menu_keys() {
+    let (mut m, mut c, mut d) = (Menu::new(false), cpu(), Debugger::new());
+    // closed, the menu only takes its own keys
+    assert!(!m.key(Key::Char('1'), &mut c, &mut d).unwrap());
+    assert!(m.key(Key::Esc, &mut c, &mut d).unwrap());
+    assert!(m.open);
+    // open, it takes them all
+    press(&mut m, &mut c, &mut d, &[Key::Char('1'), Key::Esc]);
+    assert!(!m.open);
+}
+
+#[test]
+fn test_menu_states() {
+    let (mut m, mut c, mut d) = (Menu::new(false), cpu(), Debugger::new());
+    c.frame(&[false; 16]).unwrap();
+    // save to slot 2
+    press(&mut m, &mut c, &mut d, &[Key::Esc, Key::Down, Key::Down, Key::Right, Key::Char('\n'), Key::Esc]);
+    let saved = c.clock();
+    c.frame(&[false; 16]).unwrap();
+    assert_ne!(c.clock(), saved);
+    // load it back, which closes the menu
+    press(&mut m, &mut c, &mut d, &[Key::Esc, Key::Down, Key::Char('\n')]);
+    assert!(!m.open);
+    assert_eq!(c.clock(), saved);
+    // F5 resets
+    press(&mut m, &mut c, &mut d, &[Key::F(5)]);
+    assert_eq!(c.clock(), 0.0);
+}
+
+#[test]
+fn test_menu_settings() {
+    let (mut m, mut c, mut d) = (Menu::new(false), cpu(), Debugger::new());
+    let shifting = c.quirks().shifting;
+    press(&mut m, &mut c, &mut d, &[Key::Esc, Key::Down, Key::Down, Key::Down, Key::Down, Key::Right]);
+    assert_eq!(c.ipf(), 3);
+    press(&mut m, &mut c, &mut d, &[Key::Down, Key::Down, Key::Down, Key::Char('\n')]);
+    assert_eq!(c.quirks().shifting, !shifting);
+    // quit is last
+    press(&mut m, &mut c, &mut d, &[Key::Down, Key::Down, Key::Down, Key::Down]);
+    let e = m.key(Key::Char('\n'), &mut c, &mut d).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Interrupted);
+}
+
+#[test]
+fn test_menu_locked() {
+    // while recording, nothing a movie can't replay
+    let (mut m, mut c, mut d) = (Menu::new(true), cpu(), Debugger::new());
+    c.frame(&[false; 16]).unwrap();
+    let v0 = c.clock();
+    press(&mut m, &mut c, &mut d, &[Key::F(5)]);
+    assert!(m.open);
+    assert_eq!(c.clock(), v0);
+    press(&mut m, &mut c, &mut d, &[Key::Down, Key::Down, Key::Down, Key::Down, Key::Right]);
+    assert_eq!(c.ipf(), 2);
+}
@@ -0,0 +1,64 @@
+use std::io::ErrorKind;
+use super::*;
+use crate::quirks::Platform;
+
+#[test]
+fn test_push_rle() {
+    let mut m = Movie::new(42, "std");
+    let idle = [false; 16];
+    let mut k = [false; 16]; k[0xa] = true;
+    m.push(&idle);
+    m.push(&idle);
+    m.push(&k);
+    m.push(&idle);
+    assert_eq!(m.to_text(), "c8rust-movie 2\nseed 42\nrng std\n2 0000\n1 0400\n1 0000\n");
+
+    let f: Vec<[bool; 16]> = m.frames().collect();
+    assert_eq!(f, vec![idle, idle, k, idle]);
+}
+
+#[test]
+fn test_roundtrip() {
+    let mut m = Movie::new(0xdeadbeef, "std");
+    let mut k = [false; 16]; k[0x0] = true; k[0xf] = true;
+    m.push(&k);
+    m.push(&[false; 16]);
+    assert_eq!(Movie::parse(&m.to_text()).unwrap(), m);
+}
+
+#[test]
+fn test_parse_default_rng() {
+    let m = Movie::parse("c8rust-movie 2\nseed 7\n3 0001\n").unwrap();
+    assert_eq!(m.seed, 7);
+    assert_eq!(m.rng, "std");
+    assert_eq!(m.frames().count(), 3);
+}
+
+#[test]
+fn test_parse_error() {
+    let e = Movie::parse("not a movie").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+    let e = Movie::parse("c8rust-movie 2\nseed 1\n1 zzzz\n").map_err(|e| e.kind());
+    assert_eq!(e, Err(ErrorKind::InvalidData));
+    // recorded a machine cycle at a time
+    let e = Movie::parse("c8rust-movie 1\nseed 1\n1 0000\n").unwrap_err();
+    assert!(e.to_string().contains("version 1"));
+}
+
+#[test]
+fn test_machine() {
+    // recorded on a COSMAC VIP at 15 cycles a frame
+    let mut c = Cpu::new(None).unwrap();
+    c.set_ipf(15);
+    c.set_quirks(Quirks::platform(Platform::Chip8));
+    let m = Movie::new(3, "std").machine(&c);
+    let text = m.to_text();
+    assert!(text.contains("ipf 15\nquirks vf_reset,memory,-shifting,-jumping,clipping\n"));
+    assert_eq!(Movie::parse(&text).unwrap(), m);
+
+    // replayed over other settings, which it overrides
+    let mut r = Cpu::new(None).unwrap();
+    r.set_ipf(7);
+    Movie::parse(&text).unwrap().setup(&mut r).unwrap();
+    assert_eq!((r.ipf(), r.quirks(), r.seed()), (15, c.quirks(), 3));
+}
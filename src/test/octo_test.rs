@@ -0,0 +1,112 @@
+use super::*;
+
+// a ball bouncing around the screen, as it would be written in Octo
+pub const BOUNCE: &str = "
+# bounce a ball around the screen
+: ball
+  0b01100000
+  0b11110000
+  0b11110000
+  0b01100000
+
+:const SPEED 1
+:alias x v0
+:alias y v1
+:alias dx v2
+:alias dy v3
+
+: main
+  x := 10  y := 5
+  dx := SPEED  dy := SPEED
+  i := ball
+  loop
+    sprite x y 4
+    vf := 2
+    delay := vf
+    loop
+      vf := delay
+      if vf != 0 then
+    again
+    sprite x y 4
+    x += dx
+    y += dy
+    if x == 0 then dx := 1
+    if x >= 60 then dx := -1
+    if y == 0 begin
+      dy := 1
+    else
+      if y == 28 then dy := -1
+    end
+  again
+";
+
+#[test]
+fn test_program() {
+    assert_eq!(compile(BOUNCE).unwrap(), [
+        0x12, 0x06,                         // jump main
+        0x60, 0xf0, 0xf0, 0x60,             // ball
+        0x60, 0x0a, 0x61, 0x05, 0x62, 0x01, 0x63, 0x01,
+        0xa2, 0x02,
+        0xd0, 0x14, 0x6f, 0x02, 0xff, 0x15, // 0x210: loop
+        0xff, 0x07, 0x3f, 0x00, 0x12, 0x16, // 0x216: loop .. again
+        0xd0, 0x14, 0x80, 0x24, 0x81, 0x34,
+        0x40, 0x00, 0x62, 0x01,
+        0x6f, 0x3c, 0x8f, 0x07, 0x4f, 0x01, 0x62, 0xff,
+        0x31, 0x00, 0x12, 0x36, 0x63, 0x01, // begin
+        0x12, 0x3a, 0x41, 0x1c, 0x63, 0xff, // else .. end
+        0x12, 0x10,                         // again
+    ]);
+}
+
+#[test]
+fn test_statements() {
+    let c = |s: &str| compile(&format!(": main {}", s)).unwrap();
+    assert_eq!(c("clear return ; jump main jump0 0x300 native 0x123 sprite v1 vA 15"),
+               [0x00, 0xe0, 0x00, 0xee, 0x00, 0xee, 0x12, 0x00, 0xb3, 0x00, 0x01, 0x23, 0xd1, 0xaf]);
+    assert_eq!(c("bcd v2 save v3 load v4 delay := v5 buzzer := v6"),
+               [0xf2, 0x33, 0xf3, 0x55, 0xf4, 0x65, 0xf5, 0x15, 0xf6, 0x18]);
+    assert_eq!(c("i := 0x345 i := hex v7 i += v8 v9 := random 0x7f va := key vb := delay"),
+               [0xa3, 0x45, 0xf7, 0x29, 0xf8, 0x1e, 0xc9, 0x7f, 0xfa, 0x0a, 0xfb, 0x07]);
+    assert_eq!(c("v1 := v2 v1 |= v2 v1 &= v2 v1 ^= v2 v1 += v2 v1 -= v2 v1 >>= v2 v1 =- v2 v1 <<= v2"),
+               [0x81, 0x20, 0x81, 0x21, 0x81, 0x22, 0x81, 0x23, 0x81, 0x24,
+                0x81, 0x25, 0x81, 0x26, 0x81, 0x27, 0x81, 0x2e]);
+    assert_eq!(c("v1 := 3 v1 += 3 v1 -= 3 v1 := -1"), [0x61, 0x03, 0x71, 0x03, 0x71, 0xfd, 0x61, 0xff]);
+}
+
+#[test]
+fn test_conditions() {
+    let c = |s: &str| compile(&format!(": main if {} then clear", s)).unwrap();
+    assert_eq!(c("v1 == v2"), [0x91, 0x20, 0x00, 0xe0]);
+    assert_eq!(c("v1 != 3"),  [0x31, 0x03, 0x00, 0xe0]);
+    assert_eq!(c("v1 key"),   [0xe1, 0xa1, 0x00, 0xe0]);
+    assert_eq!(c("v1 -key"),  [0xe1, 0x9e, 0x00, 0xe0]);
+    assert_eq!(c("v1 > v2"),  [0x8f, 0x20, 0x8f, 0x15, 0x3f, 0x01, 0x00, 0xe0]);
+    assert_eq!(c("v1 < 5"),   [0x6f, 0x05, 0x8f, 0x17, 0x3f, 0x01, 0x00, 0xe0]);
+    assert_eq!(c("v1 <= v2"), [0x8f, 0x20, 0x8f, 0x15, 0x4f, 0x01, 0x00, 0xe0]);
+
+    // a while leaves its loop when the condition is false
+    assert_eq!(compile(": main loop v0 += 1 while v0 != 10 again").unwrap(),
+               [0x70, 0x01, 0x40, 0x0a, 0x12, 0x08, 0x12, 0x00]);
+}
+
+#[test]
+fn test_directives() {
+    let p = compile(": main sub :call sub :unpack 0xA data : sub return : data :byte 7 :org 0x210 :byte 9").unwrap();
+    assert_eq!(p, [0x22, 0x08, 0x22, 0x08, 0x60, 0xa2, 0x61, 0x0a, 0x00, 0xee, 0x07, 0, 0, 0, 0, 0, 0x09]);
+    // :breakpoint and :monitor are only for Octo's debugger
+    assert_eq!(compile(": main :breakpoint here :monitor v0 1 clear").unwrap(), [0x00, 0xe0]);
+}
+
+#[test]
+fn test_errors() {
+    let e = |s: &str| compile(s).unwrap_err().to_string();
+    assert_eq!(e("clear"), "no main label");
+    assert_eq!(e(": main\n  clear\n  jump nowhere"), "line 3: undefined label \"nowhere\"");
+    assert!(e(": main :macro twice { clear clear }").contains(":macro isn't supported"));
+    assert!(e(": main v0 := 300").contains("doesn't fit in a byte"));
+    assert!(e(": main : loop").contains("can't be a name"));
+    assert!(e(": main : main").contains("already defined"));
+    assert!(e(": main if v0 == 1 begin clear").contains("begin without end"));
+    assert!(e(": main again").contains("again without loop"));
+    assert!(e(": main if v0 == 1 2 then clear").contains("expected \"then\" or \"begin\""));
+}
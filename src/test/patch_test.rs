@@ -0,0 +1,74 @@
+use std::io::ErrorKind;
+use super::*;
+
+// BPS number encoding, the inverse of varint
+fn encode(mut n: usize, out: &mut Vec<u8>) {
+    loop {
+        let x = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 { out.push(x | 0x80); return; }
+        out.push(x);
+        n -= 1;
+    }
+}
+
+// "hello world" -> "hello, hello!": source read, target read, target copy
+fn bps_patch(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut p = b"BPS1".to_vec();
+    encode(source.len(), &mut p);
+    encode(target.len(), &mut p);
+    encode(0, &mut p);
+    encode((5 - 1) << 2, &mut p);           // "hello"
+    encode((2 - 1) << 2 | 1, &mut p);       // ", "
+    p.extend_from_slice(b", ");
+    encode((5 - 1) << 2 | 3, &mut p);       // "hello" again, from offset 0
+    encode(0, &mut p);
+    encode(1, &mut p);                      // "!"
+    p.push(b'!');
+    p.extend_from_slice(&crc32(source).to_le_bytes());
+    p.extend_from_slice(&crc32(target).to_le_bytes());
+    let c = crc32(&p);
+    p.extend_from_slice(&c.to_le_bytes());
+    p
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn test_ips() {
+    let rom = [0x00, 0xe0, 0x12, 0x00];
+    // record at 1, run of 3 0xaa at 5
+    let p = b"PATCH\x00\x00\x01\x00\x02\x11\x22\x00\x00\x05\x00\x00\x00\x03\xaaEOF";
+    assert_eq!(apply(&rom, p).unwrap(), [0x00, 0x11, 0x22, 0x00, 0x00, 0xaa, 0xaa, 0xaa]);
+    // truncated
+    assert_eq!(apply(&rom, b"PATCHEOF\x00\x00\x02").unwrap(), [0x00, 0xe0]);
+    assert_eq!(apply(&rom, b"PATCH\x00\x00").map_err(|e| e.kind()), Err(ErrorKind::InvalidData));
+    assert!(apply(&rom, b"garbage").is_err());
+}
+
+#[test]
+fn test_make_ips() {
+    let a = b"hello world".to_vec();
+    for b in &[&b"jello world"[..], b"hello wOrld!!", b"hello", b"", b"hello world"] {
+        assert_eq!(&apply(&a, &make_ips(&a, b)).unwrap()[..], *b);
+    }
+    assert_eq!(make_ips(&a, b"hellO world"), b"PATCH\x00\x00\x04\x00\x01OEOF");
+}
+
+#[test]
+fn test_bps() {
+    let (src, tgt) = (b"hello world", b"hello, hello!");
+    let p = bps_patch(src, tgt);
+    assert_eq!(apply(src, &p).unwrap(), tgt);
+
+    // wrong source
+    let e = apply(b"jello world", &p).unwrap_err();
+    assert!(e.to_string().contains("different ROM"));
+    // corrupt patch
+    let mut bad = p.clone();
+    bad[8] ^= 1;
+    assert!(apply(src, &bad).unwrap_err().to_string().contains("checksum"));
+}
@@ -0,0 +1,40 @@
+use super::*;
+use crate::{cpu::Cpu, rom::ROM};
+
+#[test]
+fn test_counts() {
+    let mut p = Profile::new();
+    let jump = Instruction::Jump(0x200);
+    let call = Instruction::Call(0x300);
+    p.instruction(0x200, &Instruction::Load(0, 1), 0x202);
+    p.instruction(0x202, &call, 0x300);
+    p.instruction(0x300, &Instruction::Return, 0x204);
+    p.instruction(0x204, &jump, 0x200);
+    p.wait();
+    p.frame();
+
+    assert_eq!(p.hits[0x200], 1);
+    assert_eq!(p.kinds["1nnn"], 1);
+    assert_eq!(p.calls[&0x300], 1);
+    assert_eq!(p.returns, 1);
+    assert_eq!(p.waiting, 1);
+    assert_eq!(p.cycles, 5);
+    // only the jump closes a loop, not the return
+    assert_eq!(p.loops.len(), 1);
+    assert_eq!(p.loops[&(0x200, 0x204)], 1);
+}
+
+#[test]
+fn test_report() {
+    // loop: v0 += 1, call a subroutine that returns, jump back
+    let prog = [0x70, 0x01, 0x22, 0x06, 0x12, 0x00, 0x00, 0xee];
+    let mut c = Cpu::new(Some(ROM::new_prog(&prog).unwrap())).unwrap();
+    c.enable_profile();
+    for _ in 0..3 { c.frame(&[false; 16]).unwrap(); }
+    let r = c.profile_report().unwrap();
+    assert!(r.starts_with("30 instructions over 3 frames (10.0 per frame)\n"));
+    assert!(r.contains("  200-204"));            // the loop
+    assert!(r.contains("subroutines (7 returns)"));
+    assert!(r.contains("  206            8\n"));   // calls to 0x206
+    assert!(r.contains("  7xnn            8  26.67\n"));
+}
@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn test_platform() {
+    assert_eq!(Platform::from_name("XO-CHIP").unwrap(), Platform::XoChip);
+    assert!(Platform::from_name("megachip").is_err());
+    for p in &[Platform::Chip8, Platform::Schip, Platform::XoChip] {
+        assert_eq!(Quirks::platform(*p).name(), p.name());
+    }
+    assert_eq!(Quirks::default().name(), "default");
+}
+
+#[test]
+fn test_apply() {
+    let mut q = Quirks::platform(Platform::Chip8);
+    q.apply("jumping, -clipping").unwrap();
+    assert!(q.jumping && !q.clipping && q.vf_reset);
+    assert_eq!(q.name(), "custom");
+    assert_eq!(q.apply("wobbly").map_err(|e| e.kind()), Err(ErrorKind::InvalidInput));
+}
+
+#[test]
+fn test_list() {
+    let q = Quirks::platform(Platform::Chip8);
+    assert_eq!(q.list(), "vf_reset,memory,-shifting,-jumping,clipping");
+    let mut r = Quirks::default();
+    r.apply(&q.list()).unwrap();
+    assert_eq!(r, q);
+}
@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn test_seeded_reproducible() {
+    let mut a = Seeded::new(1234);
+    let mut b = Seeded::new(1234);
+    let sa: Vec<u8> = (0..32).map(|_| a.byte()).collect();
+    let sb: Vec<u8> = (0..32).map(|_| b.byte()).collect();
+    assert_eq!(sa, sb);
+
+    a.reseed(1234);
+    let sa2: Vec<u8> = (0..32).map(|_| a.byte()).collect();
+    assert_eq!(sa, sa2);
+}
+
+#[test]
+fn test_full_range() {
+    // cxnn can produce 0xff, unlike the old rand() % 255
+    let mut r = from_name("std", DEFAULT_SEED).unwrap();
+    assert!((0..100_000).any(|_| r.byte() == 0xff));
+}
+
+#[test]
+fn test_from_name() {
+    assert_eq!(from_name("std", 0).unwrap().name(), "std");
+    assert!(from_name("nope", 0).is_err());
+}
@@ -1,4 +1,4 @@
-use std::io::ErrorKind;
+use std::{env, fs, io::ErrorKind};
 use super::*;
 
 #[test]
@@ -9,3 +9,51 @@ fn test_new_prog() {
     let r = ROM::new_prog(&[0x00; RAM_SIZE]).map_err(|e| e.kind());
     assert_eq!(r, Err(ErrorKind::InvalidData));
 }
+
+#[test]
+fn test_sha1() {
+    let r = ROM::new_prog(b"abc").unwrap();
+    assert_eq!(r.len(), 3);
+    assert_eq!(r.sha1(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+}
+
+#[test]
+fn test_hex_text() {
+    let r = ROM::new_data(b"00e0 a22a\n0x60, 0x0c\n").unwrap();
+    assert_eq!(&r.rom[..r.len()], [0x00, 0xe0, 0xa2, 0x2a, 0x60, 0x0c]);
+
+    // binary that happens to be text, but not hex
+    let r = ROM::new_data(b"00e0 xyz").unwrap();
+    assert_eq!(r.len(), 8);
+    // odd digit counts aren't hex bytes
+    assert_eq!(ROM::new_data(b"abc").unwrap().len(), 3);
+    // binary 00 e0
+    assert_eq!(&ROM::new_data(&[0x00, 0xe0]).unwrap().rom[..2], [0x00, 0xe0]);
+}
+
+#[test]
+fn test_new_file() {
+    let e = ROM::new_file("/nonexistent/rom.ch8").unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+    assert!(e.to_string().starts_with("/nonexistent/rom.ch8: "));
+
+    let path = env::temp_dir().join(format!("c8rust-rom-{}.ch8", std::process::id()));
+    let p = path.to_str().unwrap();
+    fs::write(&path, vec![0x12; RAM_SIZE]).unwrap();
+    assert_eq!(ROM::new_file(p).map_err(|e| e.kind()), Err(ErrorKind::InvalidData));
+    fs::write(&path, "").unwrap();
+    assert_eq!(ROM::new_file(p).map_err(|e| e.kind()), Err(ErrorKind::InvalidData));
+    fs::write(&path, [0x12, 0x00]).unwrap();
+    assert_eq!(ROM::new_file(p).unwrap().len(), 2);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cartridge() {
+    let gif = crate::cartridge::cartridge_test::build(r#"{"options": {"tickrate": 7}, "program": ": main 0x12 0x00"}"#, 32);
+    let r = ROM::new_data(&gif).unwrap();
+    assert_eq!(&r.rom[..r.len()], [0x12, 0x00]);
+    assert_eq!(r.options().unwrap().ipf, Some(7));
+    // a patched cartridge keeps its options
+    assert_eq!(r.patch(b"PATCHEOF").unwrap().options(), r.options());
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn test_parse() {
+    let db = parse(r#"{
+        "A9993E364706816ABA3E25717850C26C9CD0D89D": {
+            "title": "Breakout", "author": "someone", "platform": "schip",
+            "quirks": { "clipping": false }, "ipf": 15, "keymap": { "j": "4", "l": "6" }
+        },
+        "0000000000000000000000000000000000000000": {}
+    }"#).unwrap();
+    let e = &db["a9993e364706816aba3e25717850c26c9cd0d89d"];
+    assert_eq!((e.title.as_str(), e.author.as_str()), ("Breakout", "someone"));
+    assert_eq!(e.platform, Some(Platform::Schip));
+    assert!(e.quirks.jumping && !e.quirks.clipping);
+    assert_eq!(e.ipf, Some(15));
+    assert_eq!(e.keymap.get(&'l'), Some(&6));
+    assert_eq!(db["0000000000000000000000000000000000000000"], Entry::default());
+}
+
+#[test]
+fn test_invalid() {
+    for bad in &[
+        r#"[]"#,
+        r#"{"00": {"platform": "megachip"}}"#,
+        r#"{"00": {"quirks": {"wobbly": true}}}"#,
+        r#"{"00": {"ipf": 0}}"#,
+        r#"{"00": {"keymap": {"j": "10"}}}"#,
+        r#"{"00": {"keymap": {"jk": "1"}}}"#,
+    ] {
+        assert!(parse(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_builtin() {
+    let db = RomDb::load(None).unwrap();
+    assert_eq!(db.get("bba0f18f285a58a1c529fc468133b631cebc483f").unwrap().title, "Opcode test");
+}
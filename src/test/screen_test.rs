@@ -0,0 +1,72 @@
+use tui::layout::Rect;
+
+use super::*;
+
+#[test]
+fn test_key_at() {
+    let pad = Rect::new(67, 2, CELL_WIDTH * 4, CELL_HEIGHT * 4);
+    // corners of the COSMAC layout
+    assert_eq!(key_at(pad, 67, 2), Some(0x1));
+    assert_eq!(key_at(pad, 67 + CELL_WIDTH * 4 - 1, 3), Some(0xc));
+    assert_eq!(key_at(pad, 67, 2 + CELL_HEIGHT * 3), Some(0xa));
+    assert_eq!(key_at(pad, 67 + CELL_WIDTH, 2 + CELL_HEIGHT * 4 - 1), Some(0x0));
+    // outside it
+    assert_eq!(key_at(pad, 66, 2), None);
+    assert_eq!(key_at(pad, 67 + CELL_WIDTH * 4, 2), None);
+    assert_eq!(key_at(pad, 67, 2 + CELL_HEIGHT * 4), None);
+    assert_eq!(key_at(Rect::default(), 0, 0), None);
+}
+
+#[test]
+fn test_renderer_fit() {
+    assert_eq!(Renderer::fit(200, 70), Some(Renderer::Block(2)));
+    assert_eq!(Renderer::fit(64, 32), Some(Renderer::Block(1)));
+    assert_eq!(Renderer::fit(100, 20), Some(Renderer::HalfBlock));
+    assert_eq!(Renderer::fit(63, 31), Some(Renderer::Braille));
+    assert_eq!(Renderer::fit(32, 8), Some(Renderer::Braille));
+    assert_eq!(Renderer::fit(31, 40), None);
+}
+
+#[test]
+fn test_areas() {
+    // 120x40 has room for everything
+    let a = Areas::new(Rect::new(0, 0, 120, 40), false).unwrap();
+    assert_eq!(a.renderer, Renderer::Block(1));
+    assert_eq!(a.display, Rect::new(1, 1, 66, 34));
+    assert_eq!(a.pad, Some(Rect::new(67, 1, PAD_WIDTH, PAD_HEIGHT)));
+    assert_eq!(a.status, Some(Rect::new(1, 38, 118, 1)));
+
+    // the debugger takes the keypad's room, then that of a larger display
+    let a = Areas::new(Rect::new(0, 0, 120, 40), true).unwrap();
+    assert_eq!((a.renderer, a.pad), (Renderer::Block(1), None));
+    assert_eq!(a.debugger, Some(Rect::new(67, 1, 52, 37)));
+    let a = Areas::new(Rect::new(0, 0, 110, 40), true).unwrap();
+    assert_eq!(a.renderer, Renderer::Braille);
+    assert_eq!(a.pad, None);
+    assert_eq!(a.debugger, Some(Rect::new(35, 1, 108 - 34, 37)));
+
+    // half height blocks for short terminals, then braille
+    let a = Areas::new(Rect::new(0, 0, 120, 21), false).unwrap();
+    assert_eq!((a.renderer, a.display.height), (Renderer::HalfBlock, 18));
+    let a = Areas::new(Rect::new(0, 0, 120, 13), false).unwrap();
+    assert_eq!((a.renderer, a.display), (Renderer::Braille, Rect::new(1, 1, 34, 10)));
+    assert_eq!(a.pad, Some(Rect::new(35, 1, PAD_WIDTH, PAD_HEIGHT)));
+
+    // down to braille, then nothing
+    let a = Areas::new(Rect::new(0, 0, 36, 13), true).unwrap();
+    assert_eq!((a.renderer, a.debugger), (Renderer::Braille, None));
+    assert_eq!(Areas::new(Rect::new(0, 0, 36, 12), false), None);
+}
+
+#[test]
+fn test_display_cells() {
+    let mut vram = [[0; V_HEIGHT]; V_WIDTH];
+    vram[0][0] = 1;
+    vram[1][3] = 1;
+    let d = |renderer| Display { vram: &vram, renderer, fg: Color::White, bg: Color::Reset };
+    assert_eq!(d(Renderer::Braille).cell(0, 0), '⢁');
+    assert_eq!(d(Renderer::HalfBlock).cell(0, 0), '▀');
+    assert_eq!(d(Renderer::HalfBlock).cell(1, 1), '▄');
+    assert_eq!(d(Renderer::Block(2)).cell(1, 1), '█');
+    assert_eq!(d(Renderer::Block(2)).cell(2, 0), ' ');
+}
@@ -0,0 +1,52 @@
+use termion::event::Key;
+
+use crate::{
+    cpu::Cpu,
+    speed::{Speed, FAST_FORWARD},
+};
+
+#[test]
+fn test_speed_frames() {
+    let mut c = Cpu::new(None).unwrap();
+    let mut s = Speed::new(false);
+    assert_eq!(s.frames(false), 1);
+    assert!(!s.key(Key::Char('1'), &mut c));
+
+    // half speed runs every other tick
+    assert!(s.key(Key::Char('\\'), &mut c));
+    let half: Vec<usize> = (0..4).map(|_| s.frames(false)).collect();
+    assert_eq!(half.iter().sum::<usize>(), 2);
+    assert!(s.describe(&c).ends_with("×½"));
+    s.key(Key::Char('\\'), &mut c);
+
+    // fast forward, until the key stops repeating
+    s.key(Key::Char('`'), &mut c);
+    assert_eq!(s.frames(false), FAST_FORWARD);
+}
+
+#[test]
+fn test_speed_advance() {
+    let mut c = Cpu::new(None).unwrap();
+    let mut s = Speed::new(false);
+    assert_eq!(s.frames(true), 0);
+    s.key(Key::Char('.'), &mut c);
+    assert_eq!(s.frames(true), 1);
+    assert_eq!(s.frames(true), 0);
+}
+
+#[test]
+fn test_speed_ipf() {
+    let mut c = Cpu::new(None).unwrap();
+    let ipf = c.ipf();
+    let mut s = Speed::new(false);
+    s.key(Key::Char(']'), &mut c);
+    assert_eq!(c.ipf(), ipf + 1);
+    assert_eq!(s.describe(&c), format!("{} cycles/frame ×1", ipf + 1));
+    for _ in 0..ipf + 5 { s.key(Key::Char('['), &mut c); }
+    assert_eq!(c.ipf(), 1);
+
+    // a movie being recorded keeps its cycles per frame
+    let mut s = Speed::new(true);
+    assert!(s.key(Key::Char(']'), &mut c));
+    assert_eq!(c.ipf(), 1);
+}
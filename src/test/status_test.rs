@@ -0,0 +1,27 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    cpu::Cpu,
+    rom::ROM,
+    status::Status,
+};
+
+#[test]
+fn test_status_rates() {
+    // fx0a: wait for a key
+    let mut c = Cpu::new(Some(ROM::new_prog(&[0xf0, 0x0a]).unwrap())).unwrap();
+    let start = Instant::now();
+    let mut s = Status::new("Flags test", &c, start);
+    for _ in 0..30 { c.frame(&[false; 16]).unwrap(); }
+
+    // measured once a second
+    s.update(&c, start + Duration::from_millis(500));
+    assert!(s.line(&c, "×1", false).starts_with("Flags test │ 0 ips   0 fps │"));
+    s.update(&c, start + Duration::from_millis(1500));
+    assert!(s.line(&c, "×1", false).starts_with("Flags test │ 200 ips  20 fps │ ×1 │ waiting for a key │"));
+
+    // a reset doesn't make the counts go backwards
+    c.reset();
+    s.update(&c, start + Duration::from_millis(2500));
+    assert!(s.line(&c, "×1", true).contains("0 ips   0 fps │ ×1 │ paused │   │ default"));
+}
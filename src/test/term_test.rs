@@ -0,0 +1,26 @@
+use std::io::ErrorKind;
+
+use signal_hook::{consts::SIGTERM, low_level};
+
+use super::*;
+
+#[test]
+fn test_signals_poll() {
+    let s = Signals::new().unwrap();
+    assert!(!s.poll().unwrap());
+
+    // noted when it arrives, acted on when polled
+    low_level::raise(SIGTERM).unwrap();
+    let e = s.poll().unwrap_err();
+    assert_eq!((e.kind(), e.to_string().as_str()), (ErrorKind::Interrupted, "stopped by SIGTERM"));
+    assert_eq!(caught(), Some(SIGTERM));
+    assert!(!s.poll().unwrap());
+}
+
+#[test]
+fn test_catch() {
+    assert_eq!(catch(|| 1), Some(1));
+    assert_eq!(catch(|| -> i32 { panic!("caught") }), None);
+    // and later panics are reported again
+    assert!(!QUIET.with(Cell::get));
+}
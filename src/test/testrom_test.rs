@@ -0,0 +1,35 @@
+// self-checking test ROMs in roms/test, run through the headless core.
+//
+// each ROM draws one 8x5 marker per test case, left to right, top to bottom,
+// in 8 columns of 6 pixel high rows: a tick for a pass, a cross for a failure
+use super::*;
+use crate::rom::ROM;
+
+const PASS: [u8; 5] = [0x00, 0x01, 0x02, 0x14, 0x08];
+const FAIL: [u8; 5] = [0x88, 0x50, 0x20, 0x50, 0x88];
+
+// run a test ROM, returning the number of each test that didn't pass
+fn failures(rom: &[u8], tests: usize) -> Vec<usize> {
+    let mut c = Cpu::new(Some(ROM::new_prog(rom).unwrap())).unwrap();
+    run(&mut c, 60, None).unwrap();
+    (0..tests).filter(|t| {
+        let (x, y) = ((t % 8) * 8, (t / 8) * 6);
+        let rows: Vec<u8> = (0..5).map(|r| {
+            (0..8).fold(0, |b, bit| b << 1 | c.vram[x + bit][y + r])
+        }).collect();
+        assert!(rows == PASS || rows == FAIL, "test {} drew no marker", t + 1);
+        rows != PASS
+    }).map(|t| t + 1).collect()
+}
+
+#[test]
+fn test_flags_rom() {
+    // 8xy4, 8xy5, 8xy7, 8xy6, 8xye results and vF, including vF as an operand
+    assert_eq!(failures(include_bytes!("../../roms/test/flags.ch8"), 30), Vec::<usize>::new());
+}
+
+#[test]
+fn test_opcodes_rom() {
+    // skips, logic, call/return, bcd, load/store, i, font, timers, bnnn, dxyn collision
+    assert_eq!(failures(include_bytes!("../../roms/test/opcodes.ch8"), 16), Vec::<usize>::new());
+}
@@ -0,0 +1,81 @@
+use std::{cell::RefCell, io, rc::Rc};
+use super::*;
+
+// writer that can still be read after being boxed into a tracer
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+impl Write for Shared {
+    fn write(&mut self, b: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(b) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+impl Shared {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone()).unwrap().lines().map(String::from).collect()
+    }
+}
+
+fn rec(cycle: u64, pc: usize) -> Record {
+    let before = Regs { v: [0; REG_COUNT], i: 0, sp: 0 };
+    let mut after = before;
+    after.v[0] = 0x21;
+    Record { cycle, pc, op: 0x6021, inst: Instruction::decode(0x6021), before, after, writes: vec![] }
+}
+
+#[test]
+fn test_text() {
+    let out = Shared::default();
+    let mut t = Tracer::new(Box::new(out.clone()), Format::Text);
+    t.record(rec(1, 0x200)).unwrap();
+    let l = &out.lines()[0];
+    assert!(l.starts_with("         1 200 6021  v0 := 0x21"));
+    assert!(l.ends_with("-> v[21 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00] i=000 sp=0"));
+}
+
+#[test]
+fn test_json() {
+    let out = Shared::default();
+    let mut t = Tracer::new(Box::new(out.clone()), Format::Json);
+    t.record(rec(7, 0x202)).unwrap();
+    assert_eq!(
+        out.lines()[0],
+        "{\"cycle\":7,\"pc\":514,\"op\":24609,\"mnemonic\":\"v0 := 0x21\",\
+         \"before\":{\"v\":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],\"i\":0,\"sp\":0},\
+         \"after\":{\"v\":[33,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],\"i\":0,\"sp\":0},\"writes\":[]}"
+    );
+}
+
+#[test]
+fn test_writes() {
+    let out = Shared::default();
+    let mut t = Tracer::new(Box::new(out.clone()), Format::Text);
+    let mut r = rec(1, 0x200);
+    r.writes = vec![(0x300, 0x02), (0x301, 0x05)];
+    t.record(r).unwrap();
+    assert!(out.lines()[0].ends_with("sp=0 w[300=02 301=05]"));
+}
+
+#[test]
+fn test_range() {
+    let out = Shared::default();
+    let mut t = Tracer::new(Box::new(out.clone()), Format::Text).range(0x202, 0x204);
+    for (ii, pc) in [0x200, 0x202, 0x204, 0x206].iter().enumerate() {
+        t.record(rec(ii as u64, *pc)).unwrap();
+    }
+    let l = out.lines();
+    assert_eq!(l.len(), 2);
+    assert!(l[0].contains(" 202 "));
+    assert!(l[1].contains(" 204 "));
+}
+
+#[test]
+fn test_ring() {
+    let out = Shared::default();
+    let mut t = Tracer::new(Box::new(out.clone()), Format::Text).last(2);
+    for c in 0..5 { t.record(rec(c, 0x200)).unwrap(); }
+    assert!(out.lines().is_empty()); // nothing written until a crash
+    t.dump().unwrap();
+    let l = out.lines();
+    assert_eq!(l.len(), 2);
+    assert!(l[0].trim_start().starts_with("3 "));
+    assert!(l[1].trim_start().starts_with("4 "));
+}
@@ -0,0 +1,88 @@
+use super::*;
+use crate::{
+    cpu::Cpu,
+    rom::ROM,
+    trace::{Format, Tracer},
+};
+use std::{cell::RefCell, io::{self, Write}, rc::Rc};
+
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+impl Write for Shared {
+    fn write(&mut self, b: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(b) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+// trace a few frames of a program in the given format
+fn trace(p: &[u8], format: Format) -> String {
+    let out = Shared::default();
+    let mut c = Cpu::new(Some(ROM::new_prog(p).unwrap())).unwrap();
+    c.set_tracer(Tracer::new(Box::new(out.clone()), format));
+    c.frame(&[false; 16]).unwrap();
+    drop(c);
+    let s = String::from_utf8(out.0.borrow().clone()).unwrap();
+    s
+}
+
+// v0 := 0xea, i := 0x300, bcd v0, loop
+const PROG: [u8; 8] = [0x60, 0xea, 0xa3, 0x00, 0xf0, 0x33, 0x12, 0x06];
+
+#[test]
+fn test_parse_own_formats() {
+    for format in &[Format::Text, Format::Json] {
+        let e = parse(&trace(&PROG, *format)).unwrap();
+        assert_eq!(e.len(), 10);
+        assert_eq!(e[0].pc, 0x200);
+        assert_eq!(e[0].op, Some(0x60ea));
+        assert_eq!(e[0].v.unwrap()[0], 0xea);
+        assert_eq!(e[1].i, Some(0x300));
+        assert_eq!(e[2].writes, Some(vec![(0x300, 2), (0x301, 3), (0x302, 4)]));
+        assert_eq!(e[3].writes, Some(vec![]));
+    }
+}
+
+#[test]
+fn test_text_json_agree() {
+    let a = parse(&trace(&PROG, Format::Text)).unwrap();
+    let b = parse(&trace(&PROG, Format::Json)).unwrap();
+    assert_eq!(first_divergence(&a, &b), None);
+}
+
+#[test]
+fn test_flat_json() {
+    let e = parse("{\"pc\": \"0x200\", \"v\": [1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0], \"i\": 5}").unwrap();
+    assert_eq!(e[0].pc, 0x200);
+    assert_eq!(e[0].v.unwrap()[0], 1);
+    assert_eq!(e[0].i, Some(5));
+    assert_eq!(e[0].op, None);
+    assert!(parse("not a trace").is_err());
+
+    // strings are only hex with a 0x prefix
+    let e = parse("{\"pc\": \"512\", \"op\": \"0x00E0\", \"i\": \"12\"}").unwrap();
+    assert_eq!(e[0].pc, 0x200);
+    assert_eq!(e[0].op, Some(0x00e0));
+    assert_eq!(e[0].i, Some(12));
+}
+
+#[test]
+fn test_divergence() {
+    let a = parse(&trace(&PROG, Format::Text)).unwrap();
+    // same program but bcd of a different value
+    let mut p = PROG; p[1] = 0xeb;
+    let b = parse(&trace(&p, Format::Json)).unwrap();
+    let d = first_divergence(&a, &b).unwrap();
+    assert_eq!(d.index, 0);
+    assert_eq!(d.fields, vec!["op: 60ea != 60eb", "v0: ea != eb"]);
+    assert!(report(&d).contains("line 1: pc 200 v0 := 0xea"));
+}
+
+#[test]
+fn test_align_and_end() {
+    let a = parse(&trace(&PROG, Format::Text)).unwrap();
+    // b has two extra leading instructions and is cut short
+    let mut b = vec![Entry { pc: 0x100, ..Default::default() }, Entry { pc: 0x102, ..Default::default() }];
+    b.extend_from_slice(&a[..5]);
+    let d = first_divergence(&a, &b).unwrap();
+    assert_eq!(d.index, 5);
+    assert_eq!(d.fields, vec!["trace b ends"]);
+}
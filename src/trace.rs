@@ -0,0 +1,161 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Error, ErrorKind, Write},
+};
+
+use crate::{
+    cpu::REG_COUNT,
+    instruction::Instruction,
+};
+
+// register file snapshot
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Regs {
+    pub v:      [u8; REG_COUNT],
+    pub i:      usize,
+    pub sp:     usize,
+}
+
+// one executed instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub cycle:  u64,
+    pub pc:     usize,
+    pub op:     u16,
+    pub inst:   Option<Instruction>,    // None for an invalid opcode
+    pub before: Regs,
+    pub after:  Regs,
+    pub writes: Vec<(usize, u8)>,       // RAM written, (address, value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,   // one aligned line per instruction
+    Json,   // JSON Lines
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _      => Err(Error::new(ErrorKind::InvalidInput, format!("unknown trace format \"{}\"", name))),
+        }
+    }
+}
+
+// writes one record per executed instruction.
+// in ring buffer mode only the last N records are kept, and they are only
+// written out when the emulator crashes (an error from the CPU, or a panic)
+pub struct Tracer {
+    out:        Box<dyn Write>,
+    format:     Format,
+    range:      Option<(usize, usize)>,     // only trace pc in [start, end]
+    ring:       Option<VecDeque<Record>>,
+    ring_len:   usize,
+}
+
+impl Tracer {
+    pub fn new(out: Box<dyn Write>, format: Format) -> Self {
+        Tracer {
+            out,
+            format,
+            range:      None,
+            ring:       None,
+            ring_len:   0,
+        }
+    }
+
+    pub fn create(path: &str, format: Format) -> Result<Self, Error> {
+        Ok(Tracer::new(Box::new(BufWriter::new(File::create(path)?)), format))
+    }
+
+    // only trace instructions at addresses in [start, end]
+    pub fn range(mut self, start: usize, end: usize) -> Self {
+        self.range = Some((start, end));
+        self
+    }
+
+    // keep only the last n records in memory, dumped on crash
+    pub fn last(mut self, n: usize) -> Self {
+        self.ring = Some(VecDeque::with_capacity(n));
+        self.ring_len = n;
+        self
+    }
+
+    pub fn record(&mut self, r: Record) -> Result<(), Error> {
+        if let Some((start, end)) = self.range {
+            if r.pc < start || r.pc > end { return Ok(()); }
+        }
+        match &mut self.ring {
+            Some(ring) => {
+                if ring.len() == self.ring_len { ring.pop_front(); }
+                ring.push_back(r);
+                Ok(())
+            },
+            None => write_record(&mut self.out, self.format, &r),
+        }
+    }
+
+    // write out the ring buffer (after a crash)
+    pub fn dump(&mut self) -> Result<(), Error> {
+        if let Some(ring) = self.ring.take() {
+            for r in ring.iter() {
+                write_record(&mut self.out, self.format, r)?;
+            }
+        }
+        self.out.flush()
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        // a panic in the CPU unwinds through here: the last instructions are the useful part
+        if std::thread::panicking() { let _ = self.dump(); }
+        let _ = self.out.flush();
+    }
+}
+
+pub fn write_record(out: &mut dyn Write, format: Format, r: &Record) -> Result<(), Error> {
+    let mnemonic = match r.inst {
+        Some(i) => i.to_string(),
+        None    => String::from("invalid"),
+    };
+    match format {
+        Format::Text => {
+            write!(
+                out,
+                "{:>10} {:03x} {:04x}  {:<24} {} -> {}",
+                r.cycle, r.pc, r.op, mnemonic, regs_text(&r.before), regs_text(&r.after)
+            )?;
+            if !r.writes.is_empty() {
+                let w: Vec<String> = r.writes.iter().map(|(a, b)| format!("{:03x}={:02x}", a, b)).collect();
+                write!(out, " w[{}]", w.join(" "))?;
+            }
+            writeln!(out)
+        },
+        Format::Json => {
+            let w: Vec<String> = r.writes.iter().map(|(a, b)| format!("[{},{}]", a, b)).collect();
+            writeln!(
+                out,
+                "{{\"cycle\":{},\"pc\":{},\"op\":{},\"mnemonic\":\"{}\",\"before\":{},\"after\":{},\"writes\":[{}]}}",
+                r.cycle, r.pc, r.op, mnemonic, regs_json(&r.before), regs_json(&r.after), w.join(",")
+            )
+        },
+    }
+}
+
+fn regs_text(r: &Regs) -> String {
+    let v: Vec<String> = r.v.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("v[{}] i={:03x} sp={:x}", v.join(" "), r.i, r.sp)
+}
+
+fn regs_json(r: &Regs) -> String {
+    let v: Vec<String> = r.v.iter().map(|b| b.to_string()).collect();
+    format!("{{\"v\":[{}],\"i\":{},\"sp\":{}}}", v.join(","), r.i, r.sp)
+}
+
+#[cfg(test)]
+#[path = "test/trace_test.rs"]
+mod trace_test;
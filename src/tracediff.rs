@@ -0,0 +1,206 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use serde_json::Value;
+
+use crate::{
+    cpu::REG_COUNT,
+    instruction::Instruction,
+};
+
+// one instruction from a trace, ours or another emulator's.
+// anything a trace doesn't record is None and isn't compared
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    pub line:   usize,                      // line number in the trace file
+    pub pc:     usize,
+    pub op:     Option<u16>,
+    pub v:      Option<[u8; REG_COUNT]>,    // registers after the instruction
+    pub i:      Option<usize>,
+    pub sp:     Option<usize>,
+    pub writes: Option<Vec<(usize, u8)>>,
+}
+
+pub fn load(path: &str) -> Result<Vec<Entry>, Error> {
+    parse(&fs::read_to_string(path)?)
+        .map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+pub fn parse(s: &str) -> Result<Vec<Entry>, Error> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(|(n, l)| {
+            let e = if l.trim_start().starts_with('{') { parse_json(l) } else { parse_text(l) };
+            e.map(|e| Entry { line: n + 1, ..e })
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("line {}: unrecognised trace record", n + 1)))
+        })
+        .collect()
+}
+
+// JSON Lines, as written by --trace-format json.
+// other emulators' flat records ({"pc": .., "v": [..], "i": ..}) are accepted
+// too, and numbers may be given as "0x.." strings
+fn parse_json(l: &str) -> Option<Entry> {
+    let j: Value = serde_json::from_str(l).ok()?;
+    let num = |v: &Value| -> Option<u64> {
+        match v {
+            Value::Number(n) => n.as_u64(),
+            // strings are decimal unless 0x prefixed
+            Value::String(s) => match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(h) => u64::from_str_radix(h, 16).ok(),
+                None    => s.parse().ok(),
+            },
+            _ => None,
+        }
+    };
+    let regs = j.get("after").unwrap_or(&j);
+    let v = regs.get("v").and_then(|v| v.as_array()).and_then(|a| {
+        let mut v = [0; REG_COUNT];
+        if a.len() != REG_COUNT { return None; }
+        for (r, x) in v.iter_mut().zip(a) { *r = num(x)? as u8; }
+        Some(v)
+    });
+    let writes = j.get("writes").and_then(|w| w.as_array()).map(|a| {
+        a.iter()
+            .filter_map(|w| Some((num(w.get(0)?)? as usize, num(w.get(1)?)? as u8)))
+            .collect()
+    });
+    Some(Entry {
+        line:   0,
+        pc:     num(j.get("pc")?)? as usize,
+        op:     j.get("op").and_then(num).map(|o| o as u16),
+        v,
+        i:      regs.get("i").and_then(num).map(|i| i as usize),
+        sp:     regs.get("sp").and_then(num).map(|s| s as usize),
+        writes,
+    })
+}
+
+// aligned text, as written by --trace-format text:
+//   cycle pc op  mnemonic v[..] i=.. sp=.. -> v[..] i=.. sp=.. [w[addr=val ..]]
+fn parse_text(l: &str) -> Option<Entry> {
+    let mut f = l.split_whitespace();
+    let _cycle = f.next()?;
+    let pc = usize::from_str_radix(f.next()?, 16).ok()?;
+    let op = u16::from_str_radix(f.next()?, 16).ok()?;
+    let (_, after) = l.split_once("->")?;
+    let (regs, writes) = match after.split_once("w[") {
+        Some((r, w)) => (r, Some(w.trim_end().trim_end_matches(']'))),
+        None         => (after, None),
+    };
+
+    let (vs, rest) = regs.trim().strip_prefix("v[")?.split_once(']')?;
+    let mut v = [0; REG_COUNT];
+    let vals: Vec<&str> = vs.split_whitespace().collect();
+    if vals.len() != REG_COUNT { return None; }
+    for (r, x) in v.iter_mut().zip(vals) { *r = u8::from_str_radix(x, 16).ok()?; }
+
+    let field = |name: &str| {
+        rest.split_whitespace()
+            .find_map(|t| t.strip_prefix(name))
+            .and_then(|x| usize::from_str_radix(x, 16).ok())
+    };
+    let writes = writes.map(|w| {
+        w.split_whitespace()
+            .filter_map(|t| {
+                let (a, b) = t.split_once('=')?;
+                Some((usize::from_str_radix(a, 16).ok()?, u8::from_str_radix(b, 16).ok()?))
+            })
+            .collect()
+    }).or_else(|| Some(Vec::new()));
+
+    Some(Entry { line: 0, pc, op: Some(op), v: Some(v), i: field("i="), sp: field("sp="), writes })
+}
+
+// where two traces first disagree
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+    pub index:  usize,          // instruction number from the aligned start
+    pub a:      Option<Entry>,  // None if the trace ended
+    pub b:      Option<Entry>,
+    pub fields: Vec<String>,    // description of each mismatch
+}
+
+// index into each trace of the first common instruction: a trace may carry
+// extra start-up instructions (e.g. another emulator's boot code)
+fn align(a: &[Entry], b: &[Entry]) -> (usize, usize) {
+    let first = |t: &[Entry], pc: usize| t.iter().position(|e| e.pc == pc);
+    let skip_b = a.first().and_then(|e| first(b, e.pc));
+    let skip_a = b.first().and_then(|e| first(a, e.pc));
+    match (skip_a, skip_b) {
+        (Some(sa), Some(sb)) if sb < sa => (0, sb),
+        (Some(sa), _)                   => (sa, 0),
+        (None, Some(sb))                => (0, sb),
+        (None, None)                    => (0, 0),
+    }
+}
+
+pub fn first_divergence(a: &[Entry], b: &[Entry]) -> Option<Divergence> {
+    let (sa, sb) = align(a, b);
+    let (a, b) = (&a[sa..], &b[sb..]);
+    for ii in 0..a.len().max(b.len()) {
+        let (ea, eb) = (a.get(ii), b.get(ii));
+        let fields = match (ea, eb) {
+            (Some(x), Some(y)) => compare(x, y),
+            (Some(_), None)    => vec![String::from("trace b ends")],
+            (None, Some(_))    => vec![String::from("trace a ends")],
+            (None, None)       => vec![],
+        };
+        if !fields.is_empty() {
+            return Some(Divergence { index: ii, a: ea.cloned(), b: eb.cloned(), fields });
+        }
+    }
+    None
+}
+
+fn compare(a: &Entry, b: &Entry) -> Vec<String> {
+    let mut d = Vec::new();
+    if a.pc != b.pc { d.push(format!("pc: {:03x} != {:03x}", a.pc, b.pc)); }
+    if let (Some(x), Some(y)) = (a.op, b.op) {
+        if x != y { d.push(format!("op: {:04x} != {:04x}", x, y)); }
+    }
+    if let (Some(x), Some(y)) = (a.v, b.v) {
+        for r in 0..REG_COUNT {
+            if x[r] != y[r] { d.push(format!("v{:x}: {:02x} != {:02x}", r, x[r], y[r])); }
+        }
+    }
+    if let (Some(x), Some(y)) = (a.i, b.i) {
+        if x != y { d.push(format!("i: {:03x} != {:03x}", x, y)); }
+    }
+    if let (Some(x), Some(y)) = (a.sp, b.sp) {
+        if x != y { d.push(format!("sp: {:x} != {:x}", x, y)); }
+    }
+    if let (Some(x), Some(y)) = (&a.writes, &b.writes) {
+        if x != y { d.push(format!("writes: {} != {}", writes_text(x), writes_text(y))); }
+    }
+    d
+}
+
+fn writes_text(w: &[(usize, u8)]) -> String {
+    let w: Vec<String> = w.iter().map(|(a, b)| format!("{:03x}={:02x}", a, b)).collect();
+    format!("[{}]", w.join(" "))
+}
+
+// human readable report of a divergence
+pub fn report(d: &Divergence) -> String {
+    let describe = |e: &Option<Entry>| match e {
+        None    => String::from("(end of trace)"),
+        Some(e) => {
+            let inst = e.op.and_then(Instruction::decode).map(|i| i.to_string()).unwrap_or_default();
+            format!("line {}: pc {:03x} {}", e.line, e.pc, inst)
+        },
+    };
+    let mut s = format!("first divergence at instruction {}\n", d.index);
+    s.push_str(&format!("  a {}\n  b {}\n", describe(&d.a), describe(&d.b)));
+    for f in &d.fields {
+        s.push_str(&format!("  {}\n", f));
+    }
+    s
+}
+
+#[cfg(test)]
+#[path = "test/tracediff_test.rs"]
+mod tracediff_test;